@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use image::io::Reader as ImageReader;
+use rustqoi::{decode, encode, ColorSpace, QoiHeader};
+
+/// go.jpg as tightly packed pixels with `channels` bytes each.
+fn go_image(channels: u8) -> (QoiHeader, Vec<u8>) {
+    let img = ImageReader::open("../go.jpg").unwrap().decode().unwrap();
+    let (header, data) = match channels {
+        3 => {
+            let img = img.into_rgb8();
+            (img.dimensions(), img.into_raw())
+        }
+        _ => {
+            let img = img.into_rgba8();
+            (img.dimensions(), img.into_raw())
+        }
+    };
+    let header = QoiHeader::new(header.0, header.1, channels, ColorSpace::Srgb);
+    (header, data)
+}
+
+/// Reports throughput in pixels, so criterion prints megapixels/s as Melem/s.
+fn pixels(header: &QoiHeader) -> Throughput {
+    Throughput::Elements(u64::from(header.width) * u64::from(header.height))
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for channels in [3, 4] {
+        let (header, data) = go_image(channels);
+        group.throughput(pixels(&header));
+        group.bench_function(format!("go/{channels}"), |b| {
+            b.iter(|| encode(black_box(&data), header).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for channels in [3, 4] {
+        let (header, data) = go_image(channels);
+        let encoded = encode(&data, header).unwrap();
+        group.throughput(pixels(&header));
+        group.bench_function(format!("go/{channels}"), |b| {
+            b.iter(|| decode(black_box(&encoded)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round_trip");
+    for channels in [3, 4] {
+        let (header, data) = go_image(channels);
+        group.throughput(pixels(&header));
+        group.bench_function(format!("go/{channels}"), |b| {
+            b.iter(|| decode(&encode(black_box(&data), header).unwrap()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode, bench_round_trip);
+criterion_main!(benches);
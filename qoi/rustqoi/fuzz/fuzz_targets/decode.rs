@@ -0,0 +1,44 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustqoi::{DecodeOptions, Decoder, PushDecoder, QoiReader};
+
+// Keeps a hostile header from making the fuzzer run out of memory, every allocation the decoders
+// make is bounded by this.
+const MAX_OUTPUT_BYTES: u64 = 1 << 24;
+
+fuzz_target!(|data: &[u8]| {
+    let options = DecodeOptions {
+        max_output_bytes: MAX_OUTPUT_BYTES,
+        ..DecodeOptions::default()
+    };
+
+    let lenient = DecodeOptions {
+        strict: false,
+        ..options
+    };
+    if let Ok(decoder) = Decoder::with_options(data, lenient) {
+        let _ = decoder.decode_with_warnings();
+    }
+
+    let Ok(expected) = Decoder::with_options(data, options).and_then(|d| d.decode()) else {
+        return;
+    };
+
+    // Whatever the in-memory decoder accepts, the streaming decoders decode identically.
+    let mut reader = QoiReader::with_options(data, options).unwrap();
+    let mut decoded = Vec::new();
+    while let Some(row) = reader.read_row().unwrap() {
+        decoded.extend_from_slice(row);
+    }
+    assert!(decoded == expected);
+
+    let mut push = PushDecoder::with_options(options);
+    let mut decoded = Vec::new();
+    for chunk in data.chunks(3) {
+        push.feed(chunk).unwrap();
+        decoded.extend(push.drain_pixels());
+    }
+    assert!(push.is_done());
+    assert!(decoded == expected);
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustqoi::{probe, read_header, DecodeOptions};
+
+fuzz_target!(|data: &[u8]| {
+    let header = read_header(data);
+    assert_eq!(probe(data), header);
+    let Ok(header) = header else {
+        return;
+    };
+    assert_eq!(&data[..4], b"qoif");
+    assert_eq!(data[4..8], header.width.to_be_bytes());
+    assert_eq!(data[8..12], header.height.to_be_bytes());
+    assert_eq!((data[12], data[13]), (header.channels, header.colorspace.into()));
+    assert!(matches!(header.channels, 3 | 4));
+
+    if let Ok(size) = DecodeOptions::unlimited().check(&header) {
+        assert_eq!(
+            size as u64,
+            header.width as u64 * header.height as u64 * header.channels as u64
+        );
+    }
+});
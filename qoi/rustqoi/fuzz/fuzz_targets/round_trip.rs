@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustqoi::{decode, encode, ColorSpace, QoiHeader, QoiWriter};
+
+// The first byte is the width, the second picks channels and colorspace, the rest are pixels.
+// The height is however many whole rows the pixels fill.
+fuzz_target!(|data: &[u8]| {
+    let [width, flags, pixels @ ..] = data else {
+        return;
+    };
+    let width = *width as u32 + 1;
+    let channels = if flags & 1 == 0 { 3 } else { 4 };
+    let colorspace = ColorSpace::try_from((flags >> 1) & 1).unwrap();
+    let row_len = width as usize * channels as usize;
+    let height = pixels.len() / row_len;
+    let pixels = &pixels[..height * row_len];
+    let header = QoiHeader::new(width, height as u32, channels, colorspace);
+
+    let encoded = encode(pixels, header).unwrap();
+    assert_eq!(decode(&encoded), Ok((header, pixels.to_vec())));
+
+    let mut writer = QoiWriter::new(Vec::new(), header).unwrap();
+    for chunk in pixels.chunks(channels as usize * 5) {
+        writer.write_row(chunk).unwrap();
+    }
+    assert!(writer.finish().unwrap() == encoded);
+});
@@ -0,0 +1,190 @@
+use image::DynamicImage;
+use rustqoi::{ColorSpace, QoiHeader, SequenceWriter};
+use std::{
+    env,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: qoiseq [options] <frames dir> <output>
+
+Builds a QOI sequence from the numbered PNG frames in a directory, e.g. frame_1.png,
+frame_2.png, ..., frame_10.png. Frames are ordered by the number at the end of their name.
+
+options:
+    --duration MS   show every frame for MS milliseconds, defaults to 100
+    --loops N       play the animation N times, 0 for forever (the default)
+    --keyframes N   store every Nth frame whole and the others as the changes from the frame
+                    before, 0 for only the first frame; defaults to 1, every frame whole
+    --channels 3|4  store RGB or RGBA, defaults to the channels of the first frame
+    --srgb          tag the frames as sRGB with linear alpha (the default)
+    --linear        tag the frames as all channels linear";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    duration_ms: u32,
+    loops: u32,
+    keyframe_interval: u32,
+    channels: Option<u8>,
+    colorspace: ColorSpace,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u32, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag} must be a whole number, got {value}"))
+}
+
+/// Returns `None` when help was requested.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut paths = Vec::new();
+    let mut duration_ms = 100;
+    let mut loops = 0;
+    let mut keyframe_interval = 1;
+    let mut channels = None;
+    let mut colorspace = ColorSpace::Srgb;
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--duration" => duration_ms = parse_number("--duration", args.next())?,
+            "--loops" => loops = parse_number("--loops", args.next())?,
+            "--keyframes" => keyframe_interval = parse_number("--keyframes", args.next())?,
+            "--channels" => match args.next().as_deref() {
+                Some("3") => channels = Some(3),
+                Some("4") => channels = Some(4),
+                Some(other) => return Err(format!("--channels must be 3 or 4, got {other}")),
+                None => return Err(String::from("--channels needs a value")),
+            },
+            "--srgb" => colorspace = ColorSpace::Srgb,
+            "--linear" => colorspace = ColorSpace::Linear,
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    match <[PathBuf; 2]>::try_from(paths) {
+        Ok([input, output]) => Ok(Some(Args {
+            input,
+            output,
+            duration_ms,
+            loops,
+            keyframe_interval,
+            channels,
+            colorspace,
+        })),
+        Err(_) => Err(String::from(
+            "expected exactly one frames directory and one output path",
+        )),
+    }
+}
+
+/// The number at the end of the file stem of a PNG, `None` for any other file.
+fn frame_number(path: &Path) -> Option<u64> {
+    if !path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+    {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    stem[stem.len() - digits..].parse().ok()
+}
+
+/// The numbered PNGs in `dir`, in order.
+fn frame_paths(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("cannot read {}: {err}", dir.display()))?;
+    let mut frames = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|err| format!("cannot read {}: {err}", dir.display()))?
+            .path();
+        if let Some(number) = frame_number(&path) {
+            frames.push((number, path));
+        }
+    }
+    if frames.is_empty() {
+        return Err(format!("no numbered PNG frames in {}", dir.display()));
+    }
+    frames.sort();
+    Ok(frames.into_iter().map(|(_, path)| path).collect())
+}
+
+fn build(args: &Args) -> Result<u32, String> {
+    let output = args.output.display();
+    let mut writer = None;
+    for path in frame_paths(&args.input)? {
+        let img =
+            image::open(&path).map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+        let writer = match &mut writer {
+            Some(writer) => writer,
+            None => {
+                let channels = args
+                    .channels
+                    .unwrap_or(if img.color().has_alpha() { 4 } else { 3 });
+                let header = QoiHeader::new(img.width(), img.height(), channels, args.colorspace);
+                let file = File::create(&args.output)
+                    .map_err(|err| format!("cannot write {output}: {err}"))?;
+                let sequence = SequenceWriter::new(BufWriter::new(file), header, args.loops)
+                    .map_err(|err| format!("cannot write {output}: {err}"))?
+                    .with_keyframe_interval(args.keyframe_interval);
+                writer.insert(sequence)
+            }
+        };
+        let header = *writer.header();
+        if (img.width(), img.height()) != (header.width, header.height) {
+            return Err(format!(
+                "{} is {}x{}, the first frame is {}x{}",
+                path.display(),
+                img.width(),
+                img.height(),
+                header.width,
+                header.height
+            ));
+        }
+        let pixels = if header.channels == 4 {
+            DynamicImage::ImageRgba8(img.into_rgba8())
+        } else {
+            DynamicImage::ImageRgb8(img.into_rgb8())
+        };
+        writer
+            .write_frame(pixels.as_bytes(), args.duration_ms)
+            .map_err(|err| format!("cannot encode {}: {err}", path.display()))?;
+    }
+    // frame_paths never returns an empty list, so there is a writer.
+    let writer = writer.unwrap();
+    let frames = writer.frames();
+    writer
+        .finish()
+        .map_err(|err| format!("cannot write {output}: {err}"))?;
+    Ok(frames)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("qoiseq: {msg}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match build(&args) {
+        Ok(frames) => {
+            println!("qoiseq: wrote {frames} frames to {}", args.output.display());
+            ExitCode::SUCCESS
+        }
+        Err(msg) => {
+            eprintln!("qoiseq: {msg}");
+            ExitCode::FAILURE
+        }
+    }
+}
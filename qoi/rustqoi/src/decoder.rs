@@ -0,0 +1,305 @@
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    format::PixelFormat,
+    header::QoiHeader,
+    metadata,
+    options::DecodeOptions,
+    pixel::{Pixel, Runner},
+    QoiError, QOI_END_MARKER, QOI_FOOTER_SIZE, QOI_HEADER_SIZE, QOI_OP_DIFF, QOI_OP_DIFF_END,
+    QOI_OP_INDEX, QOI_OP_INDEX_END, QOI_OP_LUMA, QOI_OP_LUMA_END, QOI_OP_RGB, QOI_OP_RGBA,
+    QOI_OP_RUN, QOI_OP_RUN_END,
+};
+
+/// Decodes a complete QOI file held in memory.
+pub struct Decoder<'a> {
+    header: QoiHeader,
+    body: &'a [u8],
+    decoded_size: usize,
+    format: PixelFormat,
+    premultiplied: bool,
+    strict: bool,
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    has_footer: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// Parses the header, leaving the pixel data to be decoded by [`Decoder::decode`].
+    pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self, QoiError> {
+        Decoder::with_options(data, DecodeOptions::default())
+    }
+
+    /// Like [`Decoder::new`], but rejects headers that exceed the limits in `options` and
+    /// follows its strictness.
+    pub fn with_options(
+        data: &'a (impl AsRef<[u8]> + ?Sized),
+        options: DecodeOptions,
+    ) -> Result<Self, QoiError> {
+        // Metadata after the end marker is not part of the image.
+        let data = metadata::strip_metadata(data.as_ref());
+        let min_len = if options.strict {
+            QOI_HEADER_SIZE + QOI_FOOTER_SIZE
+        } else {
+            QOI_HEADER_SIZE
+        };
+        if data.len() < min_len {
+            return Err(QoiError::TooShort);
+        }
+        let (header, body) = data.split_at(QOI_HEADER_SIZE);
+        let header = QoiHeader::try_decode(header)?;
+        let decoded_size = options.check(&header)?;
+        let has_footer = body.ends_with(&QOI_END_MARKER);
+        if options.strict && !has_footer {
+            return Err(QoiError::BadFooter);
+        }
+        // Without an end marker the file was most likely cut short, so all that is left may
+        // hold pixels.
+        let body = if has_footer {
+            &body[..body.len() - QOI_FOOTER_SIZE]
+        } else {
+            body
+        };
+        Ok(Decoder {
+            header,
+            body,
+            decoded_size,
+            format: options.format_for(&header),
+            premultiplied: options.premultiplied,
+            strict: options.strict,
+            has_footer,
+        })
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// The layout the pixels are decoded into.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// The size in bytes of the decoded pixels.
+    pub fn decoded_size(&self) -> usize {
+        self.decoded_size
+    }
+
+    /// Decodes the pixel data into a tightly packed buffer in [`Decoder::format`].
+    #[cfg(feature = "alloc")]
+    pub fn decode(&self) -> Result<Vec<u8>, QoiError> {
+        self.decode_with_warnings().map(|(out, _)| out)
+    }
+
+    /// Like [`Decoder::decode`], but also returns the problems that lenient mode tolerated.
+    /// Missing pixels repeat the last decoded pixel, surplus pixels and trailing bytes are
+    /// dropped. In strict mode the warnings are always empty.
+    #[cfg(feature = "alloc")]
+    pub fn decode_with_warnings(&self) -> Result<(Vec<u8>, Vec<QoiError>), QoiError> {
+        let mut warnings = Vec::new();
+        if !self.has_footer {
+            warnings.push(QoiError::BadFooter);
+        }
+        let mut out = vec![0; self.decoded_size];
+        self.decode_body(&mut out, &mut |err| {
+            if self.strict {
+                Err(err)
+            } else {
+                warnings.push(err);
+                Ok(())
+            }
+        })?;
+        Ok((out, warnings))
+    }
+
+    /// Like [`Decoder::decode`], but writes the pixels to the start of `out`, which must hold at
+    /// least [`Decoder::decoded_size`] bytes. In lenient mode the warnings are dropped.
+    pub fn decode_into(&self, out: &mut [u8]) -> Result<(), QoiError> {
+        let out = out
+            .get_mut(..self.decoded_size)
+            .ok_or(QoiError::BufferTooSmall)?;
+        self.decode_body(out, &mut |err| if self.strict { Err(err) } else { Ok(()) })
+    }
+
+    fn decode_body(
+        &self,
+        out: &mut [u8],
+        report: &mut impl FnMut(QoiError) -> Result<(), QoiError>,
+    ) -> Result<(), QoiError> {
+        // Every format gets its own copy of the loop, so the layout is not matched per pixel.
+        let (body, premul) = (self.body, self.premultiplied);
+        match self.format {
+            PixelFormat::Rgb => decode_body::<3>(
+                body,
+                out,
+                |p, out| PixelFormat::Rgb.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::Rgba => decode_body::<4>(
+                body,
+                out,
+                |p, out| PixelFormat::Rgba.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::Bgra => decode_body::<4>(
+                body,
+                out,
+                |p, out| PixelFormat::Bgra.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::Argb => decode_body::<4>(
+                body,
+                out,
+                |p, out| PixelFormat::Argb.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::Rgb565 => decode_body::<2>(
+                body,
+                out,
+                |p, out| PixelFormat::Rgb565.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::Luma8 => decode_body::<1>(
+                body,
+                out,
+                |p, out| PixelFormat::Luma8.write(p, out),
+                premul,
+                report,
+            ),
+            PixelFormat::LumaA8 => decode_body::<2>(
+                body,
+                out,
+                |p, out| PixelFormat::LumaA8.write(p, out),
+                premul,
+                report,
+            ),
+        }
+    }
+}
+
+fn decode_body<const N: usize>(
+    mut body: &[u8],
+    out: &mut [u8],
+    write: impl Fn(Pixel, &mut [u8]),
+    premultiplied: bool,
+    report: &mut impl FnMut(QoiError) -> Result<(), QoiError>,
+) -> Result<(), QoiError> {
+    let output = |pixel: Pixel| {
+        if premultiplied {
+            pixel.premultiplied()
+        } else {
+            pixel
+        }
+    };
+    let mut pixels_left = out.len() / N;
+    let mut out_pixels = out.chunks_exact_mut(N);
+    let mut runner = Runner::new();
+    let mut previous_pixel = Pixel::default();
+    while pixels_left > 0 {
+        let (pixel, run, tail) = match match_single_pattern(body, &mut runner, previous_pixel) {
+            Ok(Some(pattern)) => pattern,
+            Ok(None) => {
+                report(QoiError::TooFewPixels)?;
+                break;
+            }
+            Err(err) => {
+                report(err)?;
+                report(QoiError::TooFewPixels)?;
+                body = &[];
+                break;
+            }
+        };
+        previous_pixel = pixel;
+        body = tail;
+        let mut run = run as usize;
+        if run > pixels_left {
+            report(QoiError::TooManyPixels)?;
+            run = pixels_left;
+        }
+        let pixel = output(previous_pixel);
+        for out_pixel in out_pixels.by_ref().take(run) {
+            write(pixel, out_pixel);
+        }
+        pixels_left -= run;
+    }
+    let pixel = output(previous_pixel);
+    for out_pixel in out_pixels {
+        write(pixel, out_pixel);
+    }
+    if !body.is_empty() {
+        report(QoiError::TrailingData)?;
+    }
+    Ok(())
+}
+
+/// Decodes a QOI file into its header and a tightly packed RGB or RGBA buffer.
+#[cfg(feature = "alloc")]
+pub fn decode(data: &(impl AsRef<[u8]> + ?Sized)) -> Result<(QoiHeader, Vec<u8>), QoiError> {
+    let decoder = Decoder::new(data)?;
+    let out = decoder.decode()?;
+    Ok((decoder.header, out))
+}
+
+/// Like [`decode`], but writes the pixels to the start of `out`, which must hold at least
+/// [`QoiHeader::decoded_size`] bytes.
+pub fn decode_into(
+    data: &(impl AsRef<[u8]> + ?Sized),
+    out: &mut [u8],
+) -> Result<QoiHeader, QoiError> {
+    let decoder = Decoder::new(data)?;
+    decoder.decode_into(out)?;
+    Ok(decoder.header)
+}
+
+/// A decoded pixel, how often it repeats and the remaining bytestream.
+pub(crate) type Pattern<'a> = (Pixel, u8, &'a [u8]);
+
+/// The number of bytes, including `byte` itself, of the opcode that starts with `byte`.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) fn op_len(byte: u8) -> usize {
+    match byte {
+        QOI_OP_RGB => 4,
+        QOI_OP_RGBA => 5,
+        QOI_OP_LUMA..=QOI_OP_LUMA_END => 2,
+        _ => 1,
+    }
+}
+
+/// Decodes the op at the start of `body`. As in the reference decoder, every decoded pixel is
+/// stored in the index, including the one a run repeats.
+#[inline(never)]
+pub(crate) fn match_single_pattern<'a>(
+    body: &'a [u8],
+    runner: &mut Runner,
+    previous_pixel: Pixel,
+) -> Result<Option<Pattern<'a>>, QoiError> {
+    let (pixel, run, tail) = match body {
+        [QOI_OP_RGB, r, g, b, tail @ ..] => (previous_pixel.update_rgb(*r, *g, *b), 1, tail),
+        [QOI_OP_RGBA, r, g, b, a, tail @ ..] => {
+            (previous_pixel.update_rgba(*r, *g, *b, *a), 1, tail)
+        }
+        [byte @ QOI_OP_RUN..=QOI_OP_RUN_END, tail @ ..] => {
+            (previous_pixel, 1 + (*byte & !QOI_OP_RUN), tail)
+        }
+        [byte @ QOI_OP_DIFF..=QOI_OP_DIFF_END, tail @ ..] => {
+            (previous_pixel.decode_diff(*byte & !QOI_OP_RUN), 1, tail)
+        }
+        [byte @ QOI_OP_INDEX..=QOI_OP_INDEX_END, tail @ ..] => {
+            (runner.memory[(*byte & !QOI_OP_RUN) as usize], 1, tail)
+        }
+        [byte_1 @ QOI_OP_LUMA..=QOI_OP_LUMA_END, byte_2, tail @ ..] => (
+            previous_pixel.decode_luma_diff(*byte_1 & !QOI_OP_RUN, *byte_2),
+            1,
+            tail,
+        ),
+        [] => return Ok(None),
+        _ => return Err(QoiError::TruncatedOp),
+    };
+    runner.update(pixel);
+    Ok(Some((pixel, run, tail)))
+}
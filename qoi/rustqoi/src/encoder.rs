@@ -0,0 +1,324 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    format::{PixelFormat, SourceLayout},
+    header::QoiHeader,
+    pixel::{Pixel, Runner},
+    simd, QoiError, QOI_END_MARKER, QOI_FOOTER_SIZE, QOI_HEADER_SIZE, QOI_OP_INDEX, QOI_OP_RGB,
+    QOI_OP_RGBA, QOI_OP_RUN,
+};
+
+/// Encodes pixels into a QOI file, by default tightly packed RGB or RGBA matching the header.
+pub struct Encoder {
+    header: QoiHeader,
+    layout: SourceLayout,
+}
+
+impl Encoder {
+    pub fn new(header: QoiHeader) -> Self {
+        let format = PixelFormat::from_channels(header.channels);
+        Encoder {
+            header,
+            layout: SourceLayout::packed(format, header.width),
+        }
+    }
+
+    /// Reads the pixels from `layout` instead. Alpha is dropped when the header has 3 channels.
+    pub fn with_layout(mut self, layout: SourceLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    pub fn layout(&self) -> &SourceLayout {
+        &self.layout
+    }
+
+    /// Encodes `data`, which holds the pixels as described by [`Encoder::layout`].
+    #[cfg(feature = "alloc")]
+    pub fn encode(&self, data: &(impl AsRef<[u8]> + ?Sized)) -> Result<Vec<u8>, QoiError> {
+        let mut out = Vec::new();
+        self.encode_into(data, &mut out)?;
+        Ok(out)
+    }
+
+    /// Like [`Encoder::encode`], but appends the file to `out`, after reserving room for
+    /// [`max_encoded_size`] bytes. Once `out` has grown to fit, encoding another image of the
+    /// same size does not allocate.
+    #[cfg(feature = "alloc")]
+    pub fn encode_into(
+        &self,
+        data: &(impl AsRef<[u8]> + ?Sized),
+        out: &mut Vec<u8>,
+    ) -> Result<(), QoiError> {
+        self.encode_to_sink(data.as_ref(), out)
+    }
+
+    /// Like [`Encoder::encode`], but writes the file to the start of `out` and returns its
+    /// length. A buffer of [`max_encoded_size`] bytes always fits.
+    pub fn encode_to_slice(
+        &self,
+        data: &(impl AsRef<[u8]> + ?Sized),
+        out: &mut [u8],
+    ) -> Result<usize, QoiError> {
+        let mut sink = SliceSink {
+            out,
+            len: 0,
+            overflow: false,
+        };
+        self.encode_to_sink(data.as_ref(), &mut sink)?;
+        if sink.overflow {
+            return Err(QoiError::BufferTooSmall);
+        }
+        Ok(sink.len)
+    }
+
+    /// Checks the header and that `data` holds exactly the pixels it describes.
+    fn check(&self, data: &[u8]) -> Result<(), QoiError> {
+        self.header.validate()?;
+        let SourceLayout { format, stride, .. } = self.layout;
+        let row_bytes = (self.header.width as usize).saturating_mul(format.bytes_per_pixel());
+        if stride < row_bytes {
+            return Err(QoiError::InvalidStride);
+        }
+        let height = self.header.height as usize;
+        let expected = match height {
+            0 => 0,
+            _ => stride.saturating_mul(height - 1).saturating_add(row_bytes),
+        };
+        if data.len() < expected || data.len() > stride.saturating_mul(height) {
+            return Err(QoiError::DataSizeMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn encode_to_sink(&self, data: &[u8], out: &mut impl OpSink) -> Result<(), QoiError> {
+        self.check(data)?;
+        let format = self.layout.format;
+        // Only now that the data is known to hold every pixel the header claims.
+        let QoiHeader {
+            width,
+            height,
+            channels,
+            ..
+        } = self.header;
+        out.reserve(max_encoded_size(width, height, channels));
+
+        out.put(&self.header.encode());
+        // Every format gets its own copy of the loop, so the layout is not matched per pixel.
+        match format {
+            PixelFormat::Rgb => self.encode_pixels::<3>(data, |px| PixelFormat::Rgb.read(px), out),
+            PixelFormat::Rgba => {
+                self.encode_pixels::<4>(data, |px| PixelFormat::Rgba.read(px), out)
+            }
+            PixelFormat::Bgra => {
+                self.encode_pixels::<4>(data, |px| PixelFormat::Bgra.read(px), out)
+            }
+            PixelFormat::Argb => {
+                self.encode_pixels::<4>(data, |px| PixelFormat::Argb.read(px), out)
+            }
+            PixelFormat::Rgb565 => {
+                self.encode_pixels::<2>(data, |px| PixelFormat::Rgb565.read(px), out)
+            }
+            PixelFormat::Luma8 => {
+                self.encode_pixels::<1>(data, |px| PixelFormat::Luma8.read(px), out)
+            }
+            PixelFormat::LumaA8 => {
+                self.encode_pixels::<2>(data, |px| PixelFormat::LumaA8.read(px), out)
+            }
+        }
+        out.put(&QOI_END_MARKER);
+        Ok(())
+    }
+
+    /// Writes the opcodes for the pixels in `data`, which has been checked against the layout.
+    fn encode_pixels<const N: usize>(
+        &self,
+        data: &[u8],
+        read: impl Fn(&[u8]) -> Pixel,
+        out: &mut impl OpSink,
+    ) {
+        let row_bytes = self.header.width as usize * N;
+        if row_bytes == 0 {
+            return;
+        }
+        let SourceLayout {
+            stride,
+            ignore_alpha,
+            premultiplied,
+            ..
+        } = self.layout;
+        let straighten = premultiplied && !ignore_alpha;
+        let opaque = ignore_alpha || self.header.channels == 3;
+        let mut state = EncoderState::new();
+        for row in data.chunks(stride) {
+            let mut row = &row[..row_bytes];
+            while let Some((raw, rest)) = row.split_first_chunk::<N>() {
+                let mut pixel = read(raw);
+                if straighten {
+                    pixel = pixel.unpremultiplied();
+                }
+                if opaque {
+                    pixel.a = 255;
+                }
+                state.push(pixel, out);
+                // Bytes equal to these give the same pixel again, so a run is counted without
+                // reading its pixels one by one. Checking the next pixel first keeps noisy
+                // images from paying for the scan.
+                row = rest;
+                if row.starts_with(raw) {
+                    let run = simd::run_length::<N>(row, raw);
+                    state.extend_run(run, out);
+                    row = &row[run * N..];
+                }
+            }
+        }
+        state.flush_run(out);
+    }
+}
+
+/// The largest file the encoder can produce for an image of these dimensions: the header, one
+/// tag byte plus every channel for each pixel, and the end marker. Saturates at `usize::MAX`.
+pub fn max_encoded_size(width: u32, height: u32, channels: u8) -> usize {
+    (width as usize)
+        .saturating_mul(height as usize)
+        .saturating_mul(channels as usize + 1)
+        .saturating_add(QOI_HEADER_SIZE + QOI_FOOTER_SIZE)
+}
+
+/// Where [`EncoderState`] writes its opcodes.
+pub(crate) trait OpSink {
+    fn put(&mut self, op: &[u8]);
+
+    /// Makes room for `additional` more bytes, where the sink can grow.
+    fn reserve(&mut self, _additional: usize) {}
+}
+
+#[cfg(feature = "alloc")]
+impl OpSink for Vec<u8> {
+    #[inline]
+    fn put(&mut self, op: &[u8]) {
+        self.extend_from_slice(op);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+}
+
+/// Writes opcodes to the start of a fixed buffer, remembering whether any did not fit.
+struct SliceSink<'a> {
+    out: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl OpSink for SliceSink<'_> {
+    #[inline]
+    fn put(&mut self, op: &[u8]) {
+        match self.out.get_mut(self.len..self.len + op.len()) {
+            Some(dst) => {
+                dst.copy_from_slice(op);
+                self.len += op.len();
+            }
+            None => self.overflow = true,
+        }
+    }
+}
+
+/// The run length, index and previous pixel carried from one pixel to the next.
+pub(crate) struct EncoderState {
+    previous_pixel: Pixel,
+    run_length: u8,
+    runner: Runner,
+}
+
+impl EncoderState {
+    pub(crate) fn new() -> Self {
+        EncoderState {
+            previous_pixel: Pixel::default(),
+            run_length: 0,
+            runner: Runner::new(),
+        }
+    }
+
+    /// Appends the opcodes for `pixel` to `out`. A run is only written once it is broken or full,
+    /// so [`EncoderState::flush_run`] must be called after the last pixel.
+    #[inline]
+    pub(crate) fn push(&mut self, pixel: Pixel, out: &mut impl OpSink) {
+        if pixel == self.previous_pixel {
+            self.run_length += 1;
+            if self.run_length == 62 {
+                self.flush_run(out);
+            }
+        } else {
+            self.flush_run(out);
+            if let Some(ix) = self.runner.match_or_update(&pixel) {
+                out.put(&[QOI_OP_INDEX | ix]);
+            } else {
+                let raw_diff = pixel - self.previous_pixel;
+
+                if let Some(diff) = raw_diff.diff_offset() {
+                    out.put(&[diff]);
+                } else if let Some(luma_diff) = raw_diff.luma_diff_offset() {
+                    out.put(&[luma_diff.0, luma_diff.1]);
+                } else if raw_diff.a == 0 {
+                    out.put(&[QOI_OP_RGB, pixel.r, pixel.g, pixel.b]);
+                } else {
+                    out.put(&[QOI_OP_RGBA, pixel.r, pixel.g, pixel.b, pixel.a]);
+                }
+            }
+            self.previous_pixel = pixel;
+        }
+    }
+
+    /// Like pushing the previous pixel `count` more times.
+    #[inline]
+    pub(crate) fn extend_run(&mut self, count: usize, out: &mut impl OpSink) {
+        let mut run = usize::from(self.run_length) + count;
+        while run >= 62 {
+            out.put(&[QOI_OP_RUN | 61]);
+            run -= 62;
+        }
+        self.run_length = run as u8;
+    }
+
+    pub(crate) fn flush_run(&mut self, out: &mut impl OpSink) {
+        if self.run_length != 0 {
+            out.put(&[QOI_OP_RUN | (self.run_length - 1)]);
+            self.run_length = 0;
+        }
+    }
+}
+
+/// Encodes tightly packed RGB or RGBA pixels as described by `header`.
+#[cfg(feature = "alloc")]
+pub fn encode(data: &(impl AsRef<[u8]> + ?Sized), header: QoiHeader) -> Result<Vec<u8>, QoiError> {
+    Encoder::new(header).encode(data)
+}
+
+/// Like [`encode`], but appends the file to `out`.
+#[cfg(feature = "alloc")]
+pub fn encode_into(
+    data: &(impl AsRef<[u8]> + ?Sized),
+    header: QoiHeader,
+    out: &mut Vec<u8>,
+) -> Result<(), QoiError> {
+    Encoder::new(header).encode_into(data, out)
+}
+
+/// Like [`encode`], but writes the file to the start of `out` and returns its length.
+pub fn encode_to_slice(
+    data: &(impl AsRef<[u8]> + ?Sized),
+    header: QoiHeader,
+    out: &mut [u8],
+) -> Result<usize, QoiError> {
+    Encoder::new(header).encode_to_slice(data, out)
+}
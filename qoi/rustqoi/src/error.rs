@@ -0,0 +1,98 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Everything that can go wrong while encoding or decoding a QOI image.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QoiError {
+    /// The bytestream is shorter than a header plus end marker.
+    TooShort,
+    /// The bytestream does not start with `qoif`.
+    BadMagic,
+    /// The channels byte is neither 3 nor 4.
+    InvalidChannels(u8),
+    /// The colorspace byte is neither 0 nor 1.
+    InvalidColorspace(u8),
+    /// The bytestream ends in the middle of an opcode.
+    TruncatedOp,
+    /// The opcodes describe more pixels than width * height.
+    TooManyPixels,
+    /// The opcodes describe fewer pixels than width * height.
+    TooFewPixels,
+    /// There are bytes left between the last pixel and the end marker.
+    TrailingData,
+    /// The bytestream does not end with the `0,0,0,0,0,0,0,1` end marker.
+    BadFooter,
+    /// The pixel buffer handed to the encoder does not match the header dimensions and layout.
+    DataSizeMismatch { expected: usize, actual: usize },
+    /// The row stride handed to the encoder is shorter than a row of pixels.
+    InvalidStride,
+    /// The output buffer cannot hold the encoded file or the decoded pixels.
+    BufferTooSmall,
+    /// The offset table of the strip container does not match the image or the file.
+    BadStripTable,
+    /// A frame of the sequence does not have the header of the sequence.
+    BadSequence,
+    /// A delta frame does not follow a frame of the same size to build on.
+    MissingReference,
+    /// The header exceeds the limits in [`crate::DecodeOptions`].
+    LimitExceeded,
+    /// The metadata trailer after the end marker does not fit the file.
+    BadMetadata,
+    /// The underlying reader or writer failed.
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for QoiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QoiError::TooShort => write!(f, "bytestream too short"),
+            QoiError::BadMagic => write!(f, "magic is missing in header"),
+            QoiError::InvalidChannels(channels) => {
+                write!(f, "invalid number of channels: {channels}")
+            }
+            QoiError::InvalidColorspace(colorspace) => {
+                write!(f, "invalid colorspace: {colorspace}")
+            }
+            QoiError::TruncatedOp => write!(f, "bytestream ends in the middle of an opcode"),
+            QoiError::TooManyPixels => write!(f, "bytestream contains too many pixels"),
+            QoiError::TooFewPixels => write!(f, "bytestream contains too few pixels"),
+            QoiError::TrailingData => write!(f, "bytestream has data after the last pixel"),
+            QoiError::BadFooter => write!(f, "end marker is missing"),
+            QoiError::DataSizeMismatch { expected, actual } => {
+                write!(f, "expected {expected} bytes of pixel data, got {actual}")
+            }
+            QoiError::InvalidStride => write!(f, "row stride shorter than a row of pixels"),
+            QoiError::BufferTooSmall => write!(f, "output buffer too small"),
+            QoiError::BadStripTable => write!(f, "strip table does not match the image"),
+            QoiError::BadSequence => write!(f, "frame does not match the sequence header"),
+            QoiError::MissingReference => write!(f, "delta frame without a previous frame"),
+            QoiError::LimitExceeded => write!(f, "image exceeds the decode limits"),
+            QoiError::BadMetadata => write!(f, "metadata trailer is malformed"),
+            #[cfg(feature = "std")]
+            QoiError::Io(kind) => write!(f, "I/O error: {kind}"),
+        }
+    }
+}
+
+impl core::error::Error for QoiError {}
+
+#[cfg(feature = "std")]
+impl QoiError {
+    /// Maps an unexpected end of file to `eof`, since that is a property of the QOI stream rather
+    /// than of the reader.
+    pub(crate) fn from_io(err: io::Error, eof: QoiError) -> QoiError {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => eof,
+            kind => QoiError::Io(kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for QoiError {
+    fn from(err: io::Error) -> Self {
+        QoiError::Io(err.kind())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::pixel::Pixel;
+
+/// The byte layout of a pixel in memory, as written by the decoders and read by the encoder.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PixelFormat {
+    /// Red, green, blue.
+    Rgb,
+    /// Red, green, blue, alpha.
+    Rgba,
+    /// Blue, green, red, alpha.
+    Bgra,
+    /// Alpha, red, green, blue.
+    Argb,
+    /// A little-endian `u16` holding 5 bits red in the top, 6 bits green and 5 bits blue.
+    Rgb565,
+    /// Rec. 601 luma, `(77 r + 150 g + 29 b + 128) / 256`.
+    Luma8,
+    /// Luma as in [`PixelFormat::Luma8`], then alpha.
+    LumaA8,
+}
+
+impl PixelFormat {
+    /// The layout a QOI file with `channels` channels is stored in, RGB for 3 and RGBA for 4.
+    pub fn from_channels(channels: u8) -> Self {
+        if channels == 3 {
+            PixelFormat::Rgb
+        } else {
+            PixelFormat::Rgba
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Luma8 => 1,
+            PixelFormat::Rgb565 | PixelFormat::LumaA8 => 2,
+            PixelFormat::Rgb => 3,
+            PixelFormat::Rgba | PixelFormat::Bgra | PixelFormat::Argb => 4,
+        }
+    }
+
+    /// Reads the pixel at the start of `data`, which holds exactly
+    /// [`PixelFormat::bytes_per_pixel`] bytes. Layouts without alpha are opaque, and the
+    /// narrower layouts are widened back to 8 bits per channel.
+    #[inline(always)]
+    pub(crate) fn read(self, data: &[u8]) -> Pixel {
+        let (r, g, b, a) = match self {
+            PixelFormat::Rgb => (data[0], data[1], data[2], 255),
+            PixelFormat::Rgba => (data[0], data[1], data[2], data[3]),
+            PixelFormat::Bgra => (data[2], data[1], data[0], data[3]),
+            PixelFormat::Argb => (data[1], data[2], data[3], data[0]),
+            PixelFormat::Rgb565 => {
+                let rgb565 = u16::from_le_bytes([data[0], data[1]]);
+                let r = (rgb565 >> 11) as u8;
+                let g = (rgb565 >> 5) as u8 & 0x3f;
+                let b = rgb565 as u8 & 0x1f;
+                (r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255)
+            }
+            PixelFormat::Luma8 => (data[0], data[0], data[0], 255),
+            PixelFormat::LumaA8 => (data[0], data[0], data[0], data[1]),
+        };
+        Pixel { r, g, b, a }
+    }
+
+    /// Writes `pixel` to `out`, which holds exactly [`PixelFormat::bytes_per_pixel`] bytes.
+    #[inline(always)]
+    pub(crate) fn write(self, pixel: Pixel, out: &mut [u8]) {
+        let Pixel { r, g, b, a } = pixel;
+        match self {
+            PixelFormat::Rgb => out.copy_from_slice(&[r, g, b]),
+            PixelFormat::Rgba => out.copy_from_slice(&[r, g, b, a]),
+            PixelFormat::Bgra => out.copy_from_slice(&[b, g, r, a]),
+            PixelFormat::Argb => out.copy_from_slice(&[a, r, g, b]),
+            PixelFormat::Rgb565 => {
+                let rgb565 =
+                    (u16::from(r) >> 3) << 11 | (u16::from(g) >> 2) << 5 | u16::from(b) >> 3;
+                out.copy_from_slice(&rgb565.to_le_bytes());
+            }
+            PixelFormat::Luma8 => out.copy_from_slice(&[luma(pixel)]),
+            PixelFormat::LumaA8 => out.copy_from_slice(&[luma(pixel), a]),
+        }
+    }
+}
+
+#[inline(always)]
+fn luma(pixel: Pixel) -> u8 {
+    ((77 * u32::from(pixel.r) + 150 * u32::from(pixel.g) + 29 * u32::from(pixel.b) + 128) >> 8)
+        as u8
+}
+
+/// Where the encoder finds each pixel of its input: the layout of a pixel and the distance
+/// between rows, so pixels can be read straight from a framebuffer with padded rows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SourceLayout {
+    pub format: PixelFormat,
+    /// The distance in bytes from the start of one row to the start of the next. The last row
+    /// does not need to be padded.
+    pub stride: usize,
+    /// Treat the alpha byte as padding and encode every pixel as opaque, as for BGRX.
+    pub ignore_alpha: bool,
+    /// The color channels are premultiplied by alpha. They are divided back out, since QOI
+    /// stores straight alpha.
+    pub premultiplied: bool,
+}
+
+impl SourceLayout {
+    /// Rows of `width` pixels in `format` without padding between them.
+    pub fn packed(format: PixelFormat, width: u32) -> Self {
+        SourceLayout {
+            format,
+            stride: width as usize * format.bytes_per_pixel(),
+            ignore_alpha: false,
+            premultiplied: false,
+        }
+    }
+}
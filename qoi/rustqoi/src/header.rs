@@ -0,0 +1,115 @@
+#[cfg(feature = "std")]
+use std::io::Read;
+
+use crate::{QoiError, QOI_HEADER_SIZE, QOI_MAGIC};
+
+/// How the color channels of a QOI file are to be interpreted. Alpha is always linear.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ColorSpace {
+    /// sRGB color with linear alpha, stored as 0.
+    #[default]
+    Srgb,
+    /// All channels linear, stored as 1.
+    Linear,
+}
+
+impl TryFrom<u8> for ColorSpace {
+    type Error = QoiError;
+
+    fn try_from(byte: u8) -> Result<Self, QoiError> {
+        match byte {
+            0 => Ok(ColorSpace::Srgb),
+            1 => Ok(ColorSpace::Linear),
+            _ => Err(QoiError::InvalidColorspace(byte)),
+        }
+    }
+}
+
+impl From<ColorSpace> for u8 {
+    fn from(colorspace: ColorSpace) -> u8 {
+        colorspace as u8
+    }
+}
+
+/// The 14-byte header at the start of every QOI file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QoiHeader {
+    pub width: u32,
+    pub height: u32,
+    /// 3 for RGB, 4 for RGBA.
+    pub channels: u8,
+    pub colorspace: ColorSpace,
+}
+
+impl QoiHeader {
+    pub fn new(width: u32, height: u32, channels: u8, colorspace: ColorSpace) -> Self {
+        QoiHeader {
+            width,
+            height,
+            channels,
+            colorspace,
+        }
+    }
+
+    /// The number of pixels, `None` when it does not fit in `usize`.
+    pub fn n_pixels(&self) -> Option<usize> {
+        (self.width as usize).checked_mul(self.height as usize)
+    }
+
+    /// The size in bytes of the tightly packed pixel buffer that decoding produces, `None` when
+    /// it does not fit in `usize`. Headers read from a file may describe any size up to 2^64
+    /// pixels, so this is only known to fit once [`crate::DecodeOptions::check`] passed.
+    pub fn decoded_size(&self) -> Option<usize> {
+        self.n_pixels()?.checked_mul(self.channels as usize)
+    }
+
+    pub(crate) fn encode(&self) -> [u8; QOI_HEADER_SIZE] {
+        let mut header = [0; QOI_HEADER_SIZE];
+        header[..4].copy_from_slice(&QOI_MAGIC);
+        header[4..8].copy_from_slice(&self.width.to_be_bytes());
+        header[8..12].copy_from_slice(&self.height.to_be_bytes());
+        header[12] = self.channels;
+        header[13] = self.colorspace.into();
+        header
+    }
+
+    /// Checks that the channels byte holds a value allowed by the spec.
+    pub fn validate(&self) -> Result<(), QoiError> {
+        if !matches!(self.channels, 3 | 4) {
+            return Err(QoiError::InvalidChannels(self.channels));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn try_decode(data: &[u8]) -> Result<QoiHeader, QoiError> {
+        if data[..4] != QOI_MAGIC {
+            return Err(QoiError::BadMagic);
+        }
+        let width = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        let height = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+        let header = QoiHeader::new(width, height, data[12], ColorSpace::default());
+        header.validate()?;
+        Ok(QoiHeader {
+            colorspace: ColorSpace::try_from(data[13])?,
+            ..header
+        })
+    }
+}
+
+/// Validates and parses the header at the start of `data` without decoding any pixels.
+pub fn read_header(data: &[u8]) -> Result<QoiHeader, QoiError> {
+    if data.len() < QOI_HEADER_SIZE {
+        return Err(QoiError::TooShort);
+    }
+    QoiHeader::try_decode(&data[..QOI_HEADER_SIZE])
+}
+
+/// Reads exactly the 14 header bytes from `reader` and validates them.
+#[cfg(feature = "std")]
+pub fn probe(mut reader: impl Read) -> Result<QoiHeader, QoiError> {
+    let mut header = [0; QOI_HEADER_SIZE];
+    reader
+        .read_exact(&mut header)
+        .map_err(|err| QoiError::from_io(err, QoiError::TooShort))?;
+    QoiHeader::try_decode(&header)
+}
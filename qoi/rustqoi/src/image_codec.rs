@@ -0,0 +1,144 @@
+use std::io::{self, Cursor, Read, Write};
+
+use image::{
+    error::{
+        DecodingError, EncodingError, ImageFormatHint, UnsupportedError, UnsupportedErrorKind,
+    },
+    ColorType, ImageDecoder, ImageEncoder, ImageError, ImageFormat, ImageResult,
+};
+
+use crate::{ColorSpace, QoiError, QoiHeader, QoiReader, QoiWriter};
+
+fn decoding_error(err: QoiError) -> ImageError {
+    match err {
+        QoiError::Io(kind) => ImageError::IoError(io::Error::from(kind)),
+        err => ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Qoi),
+            err,
+        )),
+    }
+}
+
+fn encoding_error(err: QoiError) -> ImageError {
+    match err {
+        QoiError::Io(kind) => ImageError::IoError(io::Error::from(kind)),
+        err => ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Qoi),
+            err,
+        )),
+    }
+}
+
+/// Adapts [`QoiReader`] to [`image::ImageDecoder`], so QOI files can be loaded with
+/// `DynamicImage::from_decoder`.
+pub struct QoiImageDecoder<R: Read> {
+    reader: QoiReader<R>,
+}
+
+impl<R: Read> QoiImageDecoder<R> {
+    /// Reads and validates the header.
+    pub fn new(reader: R) -> ImageResult<Self> {
+        let reader = QoiReader::new(reader).map_err(decoding_error)?;
+        Ok(QoiImageDecoder { reader })
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        self.reader.header()
+    }
+}
+
+impl<'a, R: Read + 'a> ImageDecoder<'a> for QoiImageDecoder<R> {
+    type Reader = Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.header().width, self.header().height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        if self.header().channels == 4 {
+            ColorType::Rgba8
+        } else {
+            ColorType::Rgb8
+        }
+    }
+
+    fn into_reader(self) -> ImageResult<Self::Reader> {
+        let mut buf = vec![0; self.total_bytes() as usize];
+        self.read_image(&mut buf)?;
+        Ok(Cursor::new(buf))
+    }
+
+    fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()> {
+        assert_eq!(buf.len() as u64, self.total_bytes());
+        let channels = self.header().channels as usize;
+        let mut out = buf;
+        while !out.is_empty() {
+            let n = self.reader.read_pixels(out).map_err(decoding_error)?;
+            if n == 0 {
+                break;
+            }
+            out = &mut out[n * channels..];
+        }
+        Ok(())
+    }
+}
+
+/// Adapts [`QoiWriter`] to [`image::ImageEncoder`], so images can be saved as QOI with
+/// `DynamicImage::write_with_encoder`. Accepts 8-bit RGB and RGBA.
+pub struct QoiImageEncoder<W: Write> {
+    writer: W,
+    colorspace: ColorSpace,
+}
+
+impl<W: Write> QoiImageEncoder<W> {
+    /// Creates an encoder that tags its output as sRGB with linear alpha.
+    pub fn new(writer: W) -> Self {
+        QoiImageEncoder {
+            writer,
+            colorspace: ColorSpace::Srgb,
+        }
+    }
+
+    /// Sets the colorspace written to the header.
+    pub fn with_colorspace(mut self, colorspace: ColorSpace) -> Self {
+        self.colorspace = colorspace;
+        self
+    }
+}
+
+impl<W: Write> ImageEncoder for QoiImageEncoder<W> {
+    fn write_image(
+        self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+    ) -> ImageResult<()> {
+        let channels = match color_type {
+            ColorType::Rgb8 => 3,
+            ColorType::Rgba8 => 4,
+            _ => {
+                return Err(ImageError::Unsupported(
+                    UnsupportedError::from_format_and_kind(
+                        ImageFormatHint::Exact(ImageFormat::Qoi),
+                        UnsupportedErrorKind::Color(color_type.into()),
+                    ),
+                ))
+            }
+        };
+        let header = QoiHeader::new(width, height, channels, self.colorspace);
+        let expected = header
+            .decoded_size()
+            .ok_or_else(|| encoding_error(QoiError::LimitExceeded))?;
+        if buf.len() != expected {
+            return Err(encoding_error(QoiError::DataSizeMismatch {
+                expected,
+                actual: buf.len(),
+            }));
+        }
+        let mut writer = QoiWriter::new(self.writer, header).map_err(encoding_error)?;
+        writer.write_row(buf).map_err(encoding_error)?;
+        writer.finish().map_err(encoding_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! Encoder and decoder for the Quite OK Image format, see <https://qoiformat.org>.
+//!
+//! The codec itself only needs `core`. The `alloc` feature adds the APIs that return a `Vec`,
+//! the default `std` feature adds the adapters over `std::io`.
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod decoder;
+mod encoder;
+mod error;
+mod format;
+mod header;
+#[cfg(feature = "image")]
+mod image_codec;
+#[cfg(feature = "std")]
+mod linear;
+mod metadata;
+mod options;
+mod pixel;
+#[cfg(feature = "alloc")]
+mod push;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod sequence;
+mod simd;
+#[cfg(feature = "alloc")]
+mod strips;
+#[cfg(feature = "alloc")]
+mod temporal;
+#[cfg(feature = "std")]
+mod writer;
+
+#[cfg(feature = "alloc")]
+pub use decoder::decode;
+pub use decoder::{decode_into, Decoder};
+#[cfg(feature = "alloc")]
+pub use encoder::{encode, encode_into};
+pub use encoder::{encode_to_slice, max_encoded_size, Encoder};
+pub use error::QoiError;
+pub use format::{PixelFormat, SourceLayout};
+#[cfg(feature = "std")]
+pub use header::probe;
+pub use header::{read_header, ColorSpace, QoiHeader};
+#[cfg(feature = "image")]
+pub use image_codec::{QoiImageDecoder, QoiImageEncoder};
+#[cfg(feature = "std")]
+pub use linear::{decode_linear_f32, decode_linear_u16, encode_linear_f32, encode_linear_u16};
+#[cfg(feature = "alloc")]
+pub use metadata::set_metadata;
+pub use metadata::{read_metadata, strip_metadata, Chunk, Chunks};
+pub use options::DecodeOptions;
+#[cfg(feature = "alloc")]
+pub use push::{Progress, PushDecoder};
+#[cfg(feature = "std")]
+pub use reader::QoiReader;
+#[cfg(feature = "std")]
+pub use sequence::{is_sequence, Frame, SequenceReader, SequenceWriter};
+#[cfg(feature = "alloc")]
+pub use strips::{
+    decode_strips, encode_strips, is_strips, StripDecoder, StripEncoder, DEFAULT_STRIP_ROWS,
+};
+#[cfg(feature = "alloc")]
+pub use temporal::{is_delta, TemporalDecoder, TemporalEncoder};
+#[cfg(feature = "std")]
+pub use writer::QoiWriter;
+
+pub(crate) const QOI_HEADER_SIZE: usize = 14;
+pub(crate) const QOI_FOOTER_SIZE: usize = 8;
+pub(crate) const QOI_MAGIC: [u8; 4] = *b"qoif";
+pub(crate) const QOI_END_MARKER: [u8; QOI_FOOTER_SIZE] = [0, 0, 0, 0, 0, 0, 0, 1];
+pub(crate) const QOI_OP_RUN: u8 = 0b11000000;
+pub(crate) const QOI_OP_RUN_END: u8 = QOI_OP_RUN | 0b00111101;
+pub(crate) const QOI_OP_INDEX: u8 = 0b00000000;
+pub(crate) const QOI_OP_INDEX_END: u8 = QOI_OP_INDEX | 0b00111111;
+pub(crate) const QOI_OP_DIFF: u8 = 0b01000000;
+pub(crate) const QOI_OP_DIFF_END: u8 = QOI_OP_DIFF | 0b00111111;
+pub(crate) const QOI_OP_LUMA: u8 = 0b10000000;
+pub(crate) const QOI_OP_LUMA_END: u8 = QOI_OP_LUMA | 0b00111111;
+
+pub(crate) const QOI_OP_RGB: u8 = 0b11111110;
+pub(crate) const QOI_OP_RGBA: u8 = 0b11111111;
@@ -0,0 +1,125 @@
+use crate::{decode, ColorSpace, Encoder, QoiError, QoiHeader};
+
+/// The sRGB transfer function, from an encoded value in `0.0..=1.0` to linear light.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Maps each stored byte of a channel to linear light in `0.0..=1.0`.
+fn decode_table(colorspace: ColorSpace, alpha: bool) -> [f32; 256] {
+    let mut table = [0.0; 256];
+    for (byte, value) in table.iter_mut().enumerate() {
+        let c = byte as f32 / 255.0;
+        *value = match colorspace {
+            ColorSpace::Srgb if !alpha => srgb_to_linear(c),
+            _ => c,
+        };
+    }
+    table
+}
+
+/// Quantizes linear light to the stored byte of a channel, rounding to the nearest byte in the
+/// channel's own encoding. Values outside `0.0..=1.0` are clamped, NaN becomes 0.
+struct Quantizer {
+    /// The linear value halfway between each pair of neighbouring bytes.
+    thresholds: [f32; 255],
+}
+
+impl Quantizer {
+    fn new(colorspace: ColorSpace, alpha: bool) -> Self {
+        let mut thresholds = [0.0; 255];
+        for (byte, threshold) in thresholds.iter_mut().enumerate() {
+            let c = (byte as f32 + 0.5) / 255.0;
+            *threshold = match colorspace {
+                ColorSpace::Srgb if !alpha => srgb_to_linear(c),
+                _ => c,
+            };
+        }
+        Quantizer { thresholds }
+    }
+
+    fn quantize(&self, value: f32) -> u8 {
+        self.thresholds
+            .partition_point(|&threshold| threshold <= value) as u8
+    }
+}
+
+/// Decodes a QOI file into linear light in `0.0..=1.0`, `header.channels` values per pixel.
+/// Color is converted from sRGB unless the header says it is already linear.
+pub fn decode_linear_f32(
+    data: &(impl AsRef<[u8]> + ?Sized),
+) -> Result<(QoiHeader, Vec<f32>), QoiError> {
+    decode_linear(data.as_ref(), |value| value)
+}
+
+/// Like [`decode_linear_f32`], but scaled to `0..=65535`.
+pub fn decode_linear_u16(
+    data: &(impl AsRef<[u8]> + ?Sized),
+) -> Result<(QoiHeader, Vec<u16>), QoiError> {
+    decode_linear(data.as_ref(), |value| (value * 65535.0).round() as u16)
+}
+
+fn decode_linear<T: Copy>(
+    data: &[u8],
+    from_f32: impl Fn(f32) -> T,
+) -> Result<(QoiHeader, Vec<T>), QoiError> {
+    let (header, pixels) = decode(data)?;
+    let tables = [
+        decode_table(header.colorspace, false).map(&from_f32),
+        decode_table(header.colorspace, true).map(&from_f32),
+    ];
+    let out = pixels
+        .chunks_exact(header.channels as usize)
+        .flat_map(|pixel| {
+            pixel
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| tables[usize::from(i == 3)][byte as usize])
+        })
+        .collect();
+    Ok((header, out))
+}
+
+/// Encodes linear light in `0.0..=1.0`, `header.channels` values per pixel. Color is converted to
+/// sRGB unless `header.colorspace` is [`ColorSpace::Linear`].
+pub fn encode_linear_f32(data: &[f32], header: QoiHeader) -> Result<Vec<u8>, QoiError> {
+    encode_linear(data, header, |value| value)
+}
+
+/// Like [`encode_linear_f32`], but scaled to `0..=65535`.
+pub fn encode_linear_u16(data: &[u16], header: QoiHeader) -> Result<Vec<u8>, QoiError> {
+    encode_linear(data, header, |value| f32::from(value) / 65535.0)
+}
+
+fn encode_linear<T: Copy>(
+    data: &[T],
+    header: QoiHeader,
+    to_f32: impl Fn(T) -> f32,
+) -> Result<Vec<u8>, QoiError> {
+    header.validate()?;
+    let expected = header.decoded_size().ok_or(QoiError::LimitExceeded)?;
+    if data.len() != expected {
+        return Err(QoiError::DataSizeMismatch {
+            expected,
+            actual: data.len(),
+        });
+    }
+    let quantizers = [
+        Quantizer::new(header.colorspace, false),
+        Quantizer::new(header.colorspace, true),
+    ];
+    let pixels: Vec<u8> = data
+        .chunks_exact(header.channels as usize)
+        .flat_map(|pixel| {
+            pixel
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| quantizers[usize::from(i == 3)].quantize(to_f32(value)))
+        })
+        .collect();
+    Encoder::new(header).encode(&pixels)
+}
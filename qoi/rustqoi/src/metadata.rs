@@ -0,0 +1,139 @@
+//! Tagged metadata, such as an ICC profile or EXIF, stored after the end marker of a QOI file.
+//!
+//! ```text
+//! file           a complete QOI file, end marker included
+//! chunks         per chunk: a 4 byte tag, the length of its data as u32 BE and the data
+//! length         u32 BE    the length of all chunks together
+//! magic          4 bytes   "qoim"
+//! ```
+//!
+//! Decoders that stop at the end marker, as the reference decoder does, never see the chunks.
+//! The trailer is found from the end of the file, since the end marker itself may also occur
+//! among the opcodes.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::str;
+
+use crate::{QoiError, QOI_END_MARKER};
+
+pub(crate) const METADATA_MAGIC: [u8; 4] = *b"qoim";
+
+/// A metadata chunk, borrowed from the file it was read from or about to be written to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Chunk<'a> {
+    pub tag: [u8; 4],
+    pub data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    /// An ICC color profile.
+    pub const ICC_PROFILE: [u8; 4] = *b"iccp";
+    /// EXIF data, starting with the TIFF header as in a PNG `eXIf` chunk.
+    pub const EXIF: [u8; 4] = *b"exif";
+    /// A keyword and its UTF-8 text, separated by a 0 byte.
+    pub const TEXT: [u8; 4] = *b"text";
+
+    pub fn new(tag: [u8; 4], data: &'a [u8]) -> Self {
+        Chunk { tag, data }
+    }
+
+    /// The keyword and text of a [`Chunk::TEXT`] chunk, `None` for any other chunk or one that is
+    /// not valid UTF-8.
+    pub fn text(&self) -> Option<(&'a str, &'a str)> {
+        if self.tag != Chunk::TEXT {
+            return None;
+        }
+        let at = self.data.iter().position(|&byte| byte == 0)?;
+        let keyword = str::from_utf8(&self.data[..at]).ok()?;
+        let text = str::from_utf8(&self.data[at + 1..]).ok()?;
+        Some((keyword, text))
+    }
+
+    /// The data of a [`Chunk::TEXT`] chunk holding `keyword` and `text`.
+    #[cfg(feature = "alloc")]
+    pub fn text_data(keyword: &str, text: &str) -> Vec<u8> {
+        [keyword.as_bytes(), &[0], text.as_bytes()].concat()
+    }
+}
+
+/// Splits `data` into the QOI file and its chunks, or returns `None` when there is no trailer
+/// that fits the file.
+pub(crate) fn split_trailer(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let rest = data.strip_suffix(&METADATA_MAGIC)?;
+    let (rest, len) = rest.split_last_chunk::<4>()?;
+    let at = rest.len().checked_sub(u32::from_be_bytes(*len) as usize)?;
+    let (file, chunks) = rest.split_at(at);
+    file.ends_with(&QOI_END_MARKER).then_some((file, chunks))
+}
+
+/// The metadata chunks stored after a QOI file, in the order they were written.
+#[derive(Clone, Debug)]
+pub struct Chunks<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Chunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // read_metadata checked that every chunk fits.
+        let (tag, rest) = self.rest.split_first_chunk::<4>()?;
+        let (len, rest) = rest.split_first_chunk::<4>()?;
+        let (data, rest) = rest.split_at(u32::from_be_bytes(*len) as usize);
+        self.rest = rest;
+        Some(Chunk { tag: *tag, data })
+    }
+}
+
+/// Reads the metadata chunks after the QOI file in `data`. A file without any yields no chunks.
+pub fn read_metadata(data: &[u8]) -> Result<Chunks<'_>, QoiError> {
+    if !data.ends_with(&METADATA_MAGIC) {
+        return Ok(Chunks { rest: &[] });
+    }
+    let (_, chunks) = split_trailer(data).ok_or(QoiError::BadMetadata)?;
+    let mut rest = chunks;
+    while !rest.is_empty() {
+        let (len, tail) = rest
+            .get(4..)
+            .and_then(<[u8]>::split_first_chunk::<4>)
+            .ok_or(QoiError::BadMetadata)?;
+        rest = tail
+            .get(u32::from_be_bytes(*len) as usize..)
+            .ok_or(QoiError::BadMetadata)?;
+    }
+    Ok(Chunks { rest: chunks })
+}
+
+/// The QOI file in `data` without the metadata after it.
+pub fn strip_metadata(data: &[u8]) -> &[u8] {
+    split_trailer(data).map_or(data, |(file, _)| file)
+}
+
+/// Returns the QOI file in `data` followed by `chunks`, which replace any metadata it had.
+/// Without chunks the plain file is returned.
+#[cfg(feature = "alloc")]
+pub fn set_metadata(data: &[u8], chunks: &[Chunk]) -> Result<Vec<u8>, QoiError> {
+    let file = strip_metadata(data);
+    if !file.ends_with(&QOI_END_MARKER) {
+        return Err(QoiError::BadFooter);
+    }
+    let chunks_len = chunks
+        .iter()
+        .map(|chunk| 8 + chunk.data.len())
+        .sum::<usize>();
+    let mut out = Vec::with_capacity(file.len() + chunks_len + 8);
+    out.extend_from_slice(file);
+    if chunks.is_empty() {
+        return Ok(out);
+    }
+    let chunks_len = u32::try_from(chunks_len).map_err(|_| QoiError::LimitExceeded)?;
+    for chunk in chunks {
+        out.extend_from_slice(&chunk.tag);
+        out.extend_from_slice(&(chunk.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(chunk.data);
+    }
+    out.extend_from_slice(&chunks_len.to_be_bytes());
+    out.extend_from_slice(&METADATA_MAGIC);
+    Ok(out)
+}
@@ -0,0 +1,83 @@
+use crate::{PixelFormat, QoiError, QoiHeader};
+
+/// The largest image the reference decoder accepts.
+const QOI_PIXELS_MAX: u64 = 400_000_000;
+
+/// Limits applied to the header of an untrusted file before anything is allocated for it, and
+/// how forgiving the decoder is about the rest of the file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodeOptions {
+    pub max_width: u32,
+    pub max_height: u32,
+    pub max_pixels: u64,
+    /// The maximum size of the decoded pixel buffer.
+    pub max_output_bytes: u64,
+    /// Reject files whose opcodes do not describe exactly width * height pixels, that have bytes
+    /// left over or that lack the end marker. When off, [`crate::Decoder`] reports these as
+    /// warnings instead. The streaming decoders are always strict.
+    pub strict: bool,
+    /// The layout to decode into, or `None` for the RGB or RGBA layout of the file itself.
+    pub format: Option<PixelFormat>,
+    /// Write the color channels premultiplied by alpha.
+    pub premultiplied: bool,
+}
+
+impl Default for DecodeOptions {
+    /// Allows any image up to the 400 million pixels the reference decoder accepts.
+    fn default() -> Self {
+        DecodeOptions {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            max_pixels: QOI_PIXELS_MAX,
+            max_output_bytes: QOI_PIXELS_MAX * 4,
+            strict: true,
+            format: None,
+            premultiplied: false,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Accepts every image whose decoded size fits in memory addressable by this platform.
+    pub fn unlimited() -> Self {
+        DecodeOptions {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            max_pixels: u64::MAX,
+            max_output_bytes: u64::MAX,
+            strict: true,
+            format: None,
+            premultiplied: false,
+        }
+    }
+
+    /// The default limits, with problems in the pixel data reported as warnings.
+    pub fn lenient() -> Self {
+        DecodeOptions {
+            strict: false,
+            ..DecodeOptions::default()
+        }
+    }
+
+    /// The layout pixels of an image with this header are decoded into.
+    pub fn format_for(&self, header: &QoiHeader) -> PixelFormat {
+        self.format
+            .unwrap_or_else(|| PixelFormat::from_channels(header.channels))
+    }
+
+    /// Checks `header` against the limits and returns the decoded size in bytes.
+    pub fn check(&self, header: &QoiHeader) -> Result<usize, QoiError> {
+        if header.width > self.max_width || header.height > self.max_height {
+            return Err(QoiError::LimitExceeded);
+        }
+        let n_pixels = u64::from(header.width) * u64::from(header.height);
+        if n_pixels > self.max_pixels {
+            return Err(QoiError::LimitExceeded);
+        }
+        let size = n_pixels
+            .checked_mul(self.format_for(header).bytes_per_pixel() as u64)
+            .filter(|&size| size <= self.max_output_bytes)
+            .ok_or(QoiError::LimitExceeded)?;
+        usize::try_from(size).map_err(|_| QoiError::LimitExceeded)
+    }
+}
@@ -0,0 +1,236 @@
+use core::ops::{Add, Sub};
+
+use crate::{QOI_OP_DIFF, QOI_OP_LUMA};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Pixel {
+    pub(crate) r: u8,
+    pub(crate) g: u8,
+    pub(crate) b: u8,
+    pub(crate) a: u8,
+}
+
+impl Sub for Pixel {
+    type Output = Pixel;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Pixel {
+            r: self.r.wrapping_sub(rhs.r),
+            g: self.g.wrapping_sub(rhs.g),
+            b: self.b.wrapping_sub(rhs.b),
+            a: self.a.wrapping_sub(rhs.a),
+        }
+    }
+}
+impl Add for Pixel {
+    type Output = Pixel;
+    fn add(self, rhs: Self) -> Self::Output {
+        Pixel {
+            r: self.r.wrapping_add(rhs.r),
+            g: self.g.wrapping_add(rhs.g),
+            b: self.b.wrapping_add(rhs.b),
+            a: self.a.wrapping_add(rhs.a),
+        }
+    }
+}
+impl Add<u8> for Pixel {
+    type Output = Pixel;
+    fn add(self, rhs: u8) -> Self::Output {
+        Pixel {
+            r: self.r.wrapping_add(rhs),
+            g: self.g.wrapping_add(rhs),
+            b: self.b.wrapping_add(rhs),
+            a: self.a,
+        }
+    }
+}
+
+const LUMA_DIFF_OFFSET: Pixel = Pixel {
+    r: 8,
+    g: 32,
+    b: 8,
+    a: 0,
+};
+
+const DIFF_OFFSET: Pixel = Pixel {
+    r: 2,
+    g: 2,
+    b: 2,
+    a: 0,
+};
+
+impl Pixel {
+    /// Scales the color channels by alpha, rounding to nearest.
+    #[inline]
+    pub(crate) fn premultiplied(self) -> Pixel {
+        let scale = |c: u8| ((u16::from(c) * u16::from(self.a) + 127) / 255) as u8;
+        match self.a {
+            255 => self,
+            _ => Pixel {
+                r: scale(self.r),
+                g: scale(self.g),
+                b: scale(self.b),
+                a: self.a,
+            },
+        }
+    }
+
+    /// Divides alpha back out of premultiplied color channels, rounding to nearest. Fully
+    /// transparent pixels become transparent black.
+    #[inline]
+    pub(crate) fn unpremultiplied(self) -> Pixel {
+        let scale = |c: u8| {
+            let a = u16::from(self.a);
+            ((u16::from(c) * 255 + a / 2) / a).min(255) as u8
+        };
+        match self.a {
+            0 => Pixel::zero(),
+            255 => self,
+            _ => Pixel {
+                r: scale(self.r),
+                g: scale(self.g),
+                b: scale(self.b),
+                a: self.a,
+            },
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn decode_diff(self, data: u8) -> Pixel {
+        self - DIFF_OFFSET + Pixel::from_diff(data)
+    }
+
+    #[inline(always)]
+    pub(crate) fn decode_luma_diff(self, data_1: u8, data_2: u8) -> Pixel {
+        const LAST_FOUR: u8 = 0b00001111;
+        let dg = (data_1).wrapping_sub(32);
+        let dg_8 = dg.wrapping_sub(8);
+        Pixel {
+            r: self.r.wrapping_add(dg_8).wrapping_add(data_2 >> 4),
+            g: self.g.wrapping_add(dg),
+            b: self.b.wrapping_add(dg_8).wrapping_add(data_2 & LAST_FOUR),
+            a: self.a,
+        }
+    }
+
+    pub(crate) fn luma_diff_offset(&self) -> Option<(u8, u8)> {
+        let new = {
+            let mut t = *self + LUMA_DIFF_OFFSET;
+            t.r = t.r.wrapping_sub(self.g);
+            t.b = t.b.wrapping_sub(self.g);
+            t
+        };
+        if new.g | 63 == 63 && new.r | new.b | 15 == 15 && new.a == 0 {
+            Some((QOI_OP_LUMA | new.g, new.r << 4 | new.b))
+        } else {
+            None
+        }
+    }
+    pub(crate) fn diff_offset(&self) -> Option<u8> {
+        let new = *self + DIFF_OFFSET;
+        if new.r | new.g | new.b | 3 == 3 && new.a == 0 {
+            Some(QOI_OP_DIFF | new.r << 4 | new.g << 2 | new.b)
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn from_diff(data: u8) -> Pixel {
+        const LAST_TWO: u8 = 0b00000011;
+        Pixel {
+            r: (data >> 4) & LAST_TWO,
+            g: (data >> 2) & LAST_TWO,
+            b: data & LAST_TWO,
+            a: 0,
+        }
+    }
+    fn zero() -> Pixel {
+        Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        }
+    }
+
+    pub(crate) fn update_rgb(&self, unwrap_1: u8, unwrap_2: u8, unwrap_3: u8) -> Pixel {
+        Pixel {
+            r: unwrap_1,
+            g: unwrap_2,
+            b: unwrap_3,
+            a: self.a,
+        }
+    }
+
+    pub(crate) fn update_rgba(
+        &self,
+        unwrap_1: u8,
+        unwrap_2: u8,
+        unwrap_3: u8,
+        unwrap_4: u8,
+    ) -> Pixel {
+        Pixel {
+            r: unwrap_1,
+            g: unwrap_2,
+            b: unwrap_3,
+            a: unwrap_4,
+        }
+    }
+}
+impl Default for Pixel {
+    fn default() -> Self {
+        Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        }
+    }
+}
+impl From<&[u8]> for Pixel {
+    fn from(data: &[u8]) -> Self {
+        Pixel {
+            r: data[0],
+            g: data[1],
+            b: data[2],
+            a: if data.len() == 4 { data[3] } else { 255 },
+        }
+    }
+}
+pub(crate) struct Runner {
+    pub(crate) memory: [Pixel; 64],
+}
+
+impl Runner {
+    pub(crate) fn new() -> Self {
+        Runner {
+            memory: [Pixel::zero(); 64],
+        }
+    }
+
+    /// `(3r + 5g + 7b + 11a) % 64`, with all four products summed by a single multiplication.
+    /// The mask spreads the channels far enough apart that the partial products below the top
+    /// byte never carry into it.
+    #[inline]
+    fn hash(pixel: &Pixel) -> u8 {
+        let v = u64::from(u32::from_le_bytes([pixel.r, pixel.g, pixel.b, pixel.a]));
+        let spread = ((v << 32) | v) & 0xff00_ff00_00ff_00ff;
+        (spread.wrapping_mul(0x0300_0700_0005_000b) >> 56) as u8 & 63
+    }
+
+    #[inline]
+    pub(crate) fn match_or_update(&mut self, pixel: &Pixel) -> Option<u8> {
+        let hash = Runner::hash(pixel);
+        if pixel == &self.memory[hash as usize] {
+            Some(hash)
+        } else {
+            self.memory[hash as usize] = *pixel;
+            None
+        }
+    }
+
+    pub(crate) fn update(&mut self, pixel: Pixel) {
+        let hash = Runner::hash(&pixel);
+        self.memory[hash as usize] = pixel;
+    }
+}
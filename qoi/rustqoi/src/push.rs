@@ -0,0 +1,194 @@
+use alloc::vec::{Drain, Vec};
+
+use crate::{
+    decoder::{match_single_pattern, op_len},
+    format::PixelFormat,
+    header::QoiHeader,
+    options::DecodeOptions,
+    pixel::{Pixel, Runner},
+    QoiError, QOI_END_MARKER, QOI_FOOTER_SIZE, QOI_HEADER_SIZE,
+};
+
+/// What a call to [`PushDecoder::feed`] achieved.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Progress {
+    /// The number of bytes taken from the input. Less than its length only once the end marker
+    /// has been read, the remainder belongs to whatever follows the image.
+    pub consumed: usize,
+    /// The number of pixels that became available through [`PushDecoder::drain_pixels`].
+    pub pixels: usize,
+    /// Whether the end marker has been read.
+    pub done: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Header,
+    Body,
+    Footer,
+    Done,
+}
+
+/// Decodes a QOI stream that arrives in chunks of arbitrary size, without blocking on I/O.
+///
+/// Opcodes that are split across chunks are kept until the rest of their bytes arrive.
+pub struct PushDecoder {
+    stage: Stage,
+    partial: [u8; QOI_HEADER_SIZE],
+    partial_len: usize,
+    header: Option<QoiHeader>,
+    format: PixelFormat,
+    options: DecodeOptions,
+    runner: Runner,
+    previous_pixel: Pixel,
+    pixels_left: usize,
+    pixels: Vec<u8>,
+}
+
+impl Default for PushDecoder {
+    fn default() -> Self {
+        PushDecoder::new()
+    }
+}
+
+impl PushDecoder {
+    pub fn new() -> Self {
+        PushDecoder::with_options(DecodeOptions::default())
+    }
+
+    /// Like [`PushDecoder::new`], but rejects headers that exceed the limits in `options`.
+    pub fn with_options(options: DecodeOptions) -> Self {
+        PushDecoder {
+            stage: Stage::Header,
+            partial: [0; QOI_HEADER_SIZE],
+            partial_len: 0,
+            header: None,
+            format: PixelFormat::Rgba,
+            options,
+            runner: Runner::new(),
+            previous_pixel: Pixel::default(),
+            pixels_left: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// The header, once its 14 bytes have been fed.
+    pub fn header(&self) -> Option<&QoiHeader> {
+        self.header.as_ref()
+    }
+
+    /// Whether the end marker has been read.
+    pub fn is_done(&self) -> bool {
+        self.stage == Stage::Done
+    }
+
+    /// The layout the pixels are decoded into. Only known once the header has been fed.
+    pub fn format(&self) -> Option<PixelFormat> {
+        self.header.map(|_| self.format)
+    }
+
+    /// Decoded pixels that have not been drained yet, in [`PushDecoder::format`].
+    pub fn drain_pixels(&mut self) -> Drain<'_, u8> {
+        self.pixels.drain(..)
+    }
+
+    /// Decodes as much of `data` as possible.
+    pub fn feed(&mut self, data: &[u8]) -> Result<Progress, QoiError> {
+        let mut input = data;
+        let pixels_before = self.pixels.len();
+        loop {
+            match self.stage {
+                Stage::Header => {
+                    if !self.fill_partial(&mut input, QOI_HEADER_SIZE) {
+                        break;
+                    }
+                    let header = QoiHeader::try_decode(&self.partial)?;
+                    self.options.check(&header)?;
+                    self.header = Some(header);
+                    self.format = self.options.format_for(&header);
+                    self.pixels_left = header.n_pixels().ok_or(QoiError::LimitExceeded)?;
+                    self.stage = if self.pixels_left == 0 {
+                        Stage::Footer
+                    } else {
+                        Stage::Body
+                    };
+                }
+                Stage::Body => {
+                    let Some(&first) = input.first() else {
+                        break;
+                    };
+                    let len = op_len(if self.partial_len == 0 {
+                        first
+                    } else {
+                        self.partial[0]
+                    });
+                    if self.partial_len == 0 && input.len() >= len {
+                        let (op, tail) = input.split_at(len);
+                        self.decode_op(op)?;
+                        input = tail;
+                    } else if self.fill_partial(&mut input, len) {
+                        let op = self.partial;
+                        self.decode_op(&op[..len])?;
+                    } else {
+                        break;
+                    }
+                    if self.pixels_left == 0 {
+                        self.stage = Stage::Footer;
+                    }
+                }
+                Stage::Footer => {
+                    if !self.fill_partial(&mut input, QOI_FOOTER_SIZE) {
+                        break;
+                    }
+                    if self.partial[..QOI_FOOTER_SIZE] != QOI_END_MARKER {
+                        return Err(QoiError::BadFooter);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => break,
+            }
+        }
+        Ok(Progress {
+            consumed: data.len() - input.len(),
+            pixels: (self.pixels.len() - pixels_before) / self.format.bytes_per_pixel(),
+            done: self.is_done(),
+        })
+    }
+
+    /// Moves bytes from `input` into the partial buffer until it holds `len` bytes. Returns
+    /// whether it does, in which case the buffer is reset for the next use.
+    fn fill_partial(&mut self, input: &mut &[u8], len: usize) -> bool {
+        let n = (len - self.partial_len).min(input.len());
+        self.partial[self.partial_len..self.partial_len + n].copy_from_slice(&input[..n]);
+        self.partial_len += n;
+        *input = &input[n..];
+        if self.partial_len == len {
+            self.partial_len = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn decode_op(&mut self, op: &[u8]) -> Result<(), QoiError> {
+        let (pixel, run, _) = match_single_pattern(op, &mut self.runner, self.previous_pixel)?
+            .ok_or(QoiError::TruncatedOp)?;
+        if run as usize > self.pixels_left {
+            return Err(QoiError::TooManyPixels);
+        }
+        self.previous_pixel = pixel;
+        self.pixels_left -= run as usize;
+        let mut bytes = [0; 4];
+        let bytes = &mut bytes[..self.format.bytes_per_pixel()];
+        let pixel = if self.options.premultiplied {
+            pixel.premultiplied()
+        } else {
+            pixel
+        };
+        self.format.write(pixel, bytes);
+        for _ in 0..run {
+            self.pixels.extend_from_slice(bytes);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::{BufReader, Read};
+
+use crate::{
+    decoder::{match_single_pattern, op_len},
+    format::PixelFormat,
+    header::{probe, QoiHeader},
+    options::DecodeOptions,
+    pixel::{Pixel, Runner},
+    QoiError, QOI_END_MARKER, QOI_FOOTER_SIZE,
+};
+
+/// Decodes a QOI stream incrementally from a reader.
+///
+/// Only the index, the previous pixel and a pending run are kept between calls, so the pixels can
+/// be consumed row by row without holding the whole image in memory.
+pub struct QoiReader<R: Read> {
+    reader: BufReader<R>,
+    header: QoiHeader,
+    format: PixelFormat,
+    premultiplied: bool,
+    runner: Runner,
+    previous_pixel: Pixel,
+    /// The previous pixel as it is written out.
+    output_pixel: Pixel,
+    run: u8,
+    pixels_left: usize,
+    row: Vec<u8>,
+}
+
+impl<R: Read> QoiReader<R> {
+    /// Reads and validates the header.
+    pub fn new(reader: R) -> Result<Self, QoiError> {
+        QoiReader::with_options(reader, DecodeOptions::default())
+    }
+
+    /// Like [`QoiReader::new`], but rejects headers that exceed the limits in `options`.
+    pub fn with_options(reader: R, options: DecodeOptions) -> Result<Self, QoiError> {
+        let mut reader = BufReader::new(reader);
+        let header = probe(&mut reader)?;
+        options.check(&header)?;
+        let mut qoi_reader = QoiReader {
+            reader,
+            header,
+            format: options.format_for(&header),
+            premultiplied: options.premultiplied,
+            runner: Runner::new(),
+            previous_pixel: Pixel::default(),
+            output_pixel: Pixel::default(),
+            run: 0,
+            pixels_left: header.n_pixels().ok_or(QoiError::LimitExceeded)?,
+            row: Vec::new(),
+        };
+        if qoi_reader.pixels_left == 0 {
+            qoi_reader.read_footer()?;
+        }
+        Ok(qoi_reader)
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// The layout the pixels are decoded into.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// The number of pixels that have not been read yet.
+    pub fn pixels_left(&self) -> usize {
+        self.pixels_left
+    }
+
+    /// Hands back the reader. Its buffer may already hold bytes from after the end marker, such
+    /// as the next image of a stream, so read on from the returned `BufReader` rather than
+    /// unwrapping it further.
+    pub fn into_inner(self) -> BufReader<R> {
+        self.reader
+    }
+
+    /// Fills `buf` with as many whole pixels as fit in [`QoiReader::format`], and returns the
+    /// number of pixels written. Returns 0 once the image is exhausted.
+    pub fn read_pixels(&mut self, buf: &mut [u8]) -> Result<usize, QoiError> {
+        let mut written = 0;
+        for out_pixel in buf.chunks_exact_mut(self.format.bytes_per_pixel()) {
+            if self.pixels_left == 0 {
+                break;
+            }
+            if self.run == 0 {
+                self.read_op()?;
+            }
+            self.format.write(self.output_pixel, out_pixel);
+            self.run -= 1;
+            self.pixels_left -= 1;
+            written += 1;
+            if self.pixels_left == 0 {
+                self.read_footer()?;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Decodes the next row into an internal scanline buffer, or returns `None` after the last
+    /// row.
+    pub fn read_row(&mut self) -> Result<Option<&[u8]>, QoiError> {
+        if self.pixels_left == 0 {
+            return Ok(None);
+        }
+        let mut row = std::mem::take(&mut self.row);
+        row.resize(
+            self.header.width as usize * self.format.bytes_per_pixel(),
+            0,
+        );
+        let result = self.read_pixels(&mut row);
+        self.row = row;
+        result?;
+        Ok(Some(&self.row))
+    }
+
+    fn read_op(&mut self) -> Result<(), QoiError> {
+        let mut op = [0; 5];
+        self.reader
+            .read_exact(&mut op[..1])
+            .map_err(|err| QoiError::from_io(err, QoiError::TooFewPixels))?;
+        let len = op_len(op[0]);
+        self.reader
+            .read_exact(&mut op[1..len])
+            .map_err(|err| QoiError::from_io(err, QoiError::TruncatedOp))?;
+        let (pixel, run, _) =
+            match_single_pattern(&op[..len], &mut self.runner, self.previous_pixel)?
+                .ok_or(QoiError::TruncatedOp)?;
+        if run as usize > self.pixels_left {
+            return Err(QoiError::TooManyPixels);
+        }
+        self.previous_pixel = pixel;
+        self.output_pixel = if self.premultiplied {
+            pixel.premultiplied()
+        } else {
+            pixel
+        };
+        self.run = run;
+        Ok(())
+    }
+
+    fn read_footer(&mut self) -> Result<(), QoiError> {
+        let mut footer = [0; QOI_FOOTER_SIZE];
+        self.reader
+            .read_exact(&mut footer)
+            .map_err(|err| QoiError::from_io(err, QoiError::BadFooter))?;
+        if footer != QOI_END_MARKER {
+            return Err(QoiError::BadFooter);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+//! A container for animations: a sequence of QOI frames that share one header.
+//!
+//! ```text
+//! magic          4 bytes   "qoiq"
+//! header         14 bytes  the QOI header every frame has
+//! frames         u32 BE    the number of frames
+//! loops          u32 BE    how often the animation plays, 0 for forever
+//! frames         per frame: its duration in milliseconds as u32 BE, its length as u64 BE and
+//!                a complete QOI file of that length
+//! ```
+//!
+//! Frames after the first may also be delta frames, see [`crate::TemporalEncoder`].
+
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+
+use crate::{
+    format::PixelFormat, header::QoiHeader, options::DecodeOptions, pixel::Pixel, QoiError,
+    TemporalDecoder, TemporalEncoder, QOI_HEADER_SIZE,
+};
+
+pub(crate) const SEQUENCE_MAGIC: [u8; 4] = *b"qoiq";
+
+const PREFIX_SIZE: usize = 4 + QOI_HEADER_SIZE + 4 + 4;
+
+/// A decoded frame of a sequence.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Frame {
+    /// Tightly packed pixels in the layout picked by the options.
+    pub pixels: Vec<u8>,
+    /// How long the frame is shown, in milliseconds.
+    pub duration_ms: u32,
+}
+
+/// Encodes frames one by one into a sequence.
+///
+/// The number of frames is only known at the end, so [`SequenceWriter::finish`] seeks back to
+/// fill it in.
+pub struct SequenceWriter<W: Write + Seek> {
+    writer: W,
+    header: QoiHeader,
+    /// Where the sequence starts in `writer`.
+    start: u64,
+    frames: u32,
+    encoder: TemporalEncoder,
+    keyframe_interval: u32,
+}
+
+impl<W: Write + Seek> SequenceWriter<W> {
+    /// Validates and writes the header at the current position of `writer`.
+    pub fn new(mut writer: W, header: QoiHeader, loops: u32) -> Result<Self, QoiError> {
+        header.validate()?;
+        let start = writer.stream_position()?;
+        let mut prefix = Vec::with_capacity(PREFIX_SIZE);
+        prefix.extend_from_slice(&SEQUENCE_MAGIC);
+        prefix.extend_from_slice(&header.encode());
+        prefix.extend_from_slice(&0u32.to_be_bytes());
+        prefix.extend_from_slice(&loops.to_be_bytes());
+        writer.write_all(&prefix)?;
+        Ok(SequenceWriter {
+            writer,
+            header,
+            start,
+            frames: 0,
+            encoder: TemporalEncoder::new(header),
+            keyframe_interval: 1,
+        })
+    }
+
+    /// Makes every `interval`th frame a key frame and the others delta frames, which only store
+    /// what changed since the frame before. With 0 only the first frame is a key frame. Defaults
+    /// to 1, every frame a plain QOI file.
+    pub fn with_keyframe_interval(mut self, interval: u32) -> Self {
+        self.keyframe_interval = interval;
+        self
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// The number of frames written so far.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Encodes `data`, which holds `header.channels` bytes per pixel, as the next frame.
+    pub fn write_frame(&mut self, data: &[u8], duration_ms: u32) -> Result<(), QoiError> {
+        if self.frames == u32::MAX {
+            return Err(QoiError::LimitExceeded);
+        }
+        if self.keyframe_interval != 0 && self.frames.is_multiple_of(self.keyframe_interval) {
+            self.encoder.reset();
+        }
+        let encoded = self.encoder.encode_frame(data)?;
+        let mut record = Vec::with_capacity(12 + encoded.len());
+        record.extend_from_slice(&duration_ms.to_be_bytes());
+        record.extend_from_slice(&(encoded.len() as u64).to_be_bytes());
+        record.extend_from_slice(&encoded);
+        self.writer.write_all(&record)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Fills in the number of frames and hands back the writer, positioned after the last
+    /// frame.
+    pub fn finish(mut self) -> Result<W, QoiError> {
+        let end = self.writer.stream_position()?;
+        let count_at = self.start + (PREFIX_SIZE - 8) as u64;
+        self.writer.seek(SeekFrom::Start(count_at))?;
+        self.writer.write_all(&self.frames.to_be_bytes())?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Decodes the frames of a sequence from a reader, one at a time as it is iterated.
+pub struct SequenceReader<R: Read> {
+    reader: BufReader<R>,
+    header: QoiHeader,
+    options: DecodeOptions,
+    decoder: TemporalDecoder,
+    loops: u32,
+    frames: u32,
+    frames_left: u32,
+}
+
+impl<R: Read> SequenceReader<R> {
+    /// Reads and validates the header of the sequence.
+    pub fn new(reader: R) -> Result<Self, QoiError> {
+        SequenceReader::with_options(reader, DecodeOptions::default())
+    }
+
+    /// Like [`SequenceReader::new`], but applies `options` to every frame.
+    pub fn with_options(reader: R, options: DecodeOptions) -> Result<Self, QoiError> {
+        let mut reader = BufReader::new(reader);
+        let mut prefix = [0; PREFIX_SIZE];
+        reader
+            .read_exact(&mut prefix)
+            .map_err(|err| QoiError::from_io(err, QoiError::TooShort))?;
+        if prefix[..4] != SEQUENCE_MAGIC {
+            return Err(QoiError::BadMagic);
+        }
+        let header = QoiHeader::try_decode(&prefix[4..4 + QOI_HEADER_SIZE])?;
+        options.check(&header)?;
+        let read_u32 = |at: usize| u32::from_be_bytes(prefix[at..at + 4].try_into().unwrap());
+        let frames = read_u32(PREFIX_SIZE - 8);
+        Ok(SequenceReader {
+            reader,
+            header,
+            options,
+            decoder: TemporalDecoder::with_options(options),
+            loops: read_u32(PREFIX_SIZE - 4),
+            frames,
+            frames_left: frames,
+        })
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// How often the animation plays, 0 for forever.
+    pub fn loops(&self) -> u32 {
+        self.loops
+    }
+
+    /// The number of frames in the sequence.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// The number of frames that have not been read yet.
+    pub fn frames_left(&self) -> u32 {
+        self.frames_left
+    }
+
+    /// Reads the next frame, or returns `None` once all frames have been read.
+    pub fn read_frame(&mut self) -> Result<Option<Frame>, QoiError> {
+        if self.frames_left == 0 {
+            return Ok(None);
+        }
+        let mut record = [0; 12];
+        self.reader
+            .read_exact(&mut record)
+            .map_err(|err| QoiError::from_io(err, QoiError::TooShort))?;
+        let duration_ms = u32::from_be_bytes(record[..4].try_into().unwrap());
+        let len = u64::from_be_bytes(record[4..].try_into().unwrap());
+        // The length is not trusted with an allocation up front, a lying file runs out first.
+        let mut encoded = Vec::new();
+        self.reader.by_ref().take(len).read_to_end(&mut encoded)?;
+        if encoded.len() as u64 != len {
+            return Err(QoiError::TooShort);
+        }
+
+        let (header, pixels) = self.decoder.decode_frame(&encoded)?;
+        if header != self.header {
+            return Err(QoiError::BadSequence);
+        }
+        let pixels = convert(pixels, &header, &self.options);
+        self.frames_left -= 1;
+        Ok(Some(Frame {
+            pixels,
+            duration_ms,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for SequenceReader<R> {
+    type Item = Result<Frame, QoiError>;
+
+    /// Stops after the first error.
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.read_frame().transpose();
+        if let Some(Err(_)) = frame {
+            self.frames_left = 0;
+        }
+        frame
+    }
+}
+
+/// Converts a frame from the layout of the file to the one picked by `options`.
+fn convert(pixels: &[u8], header: &QoiHeader, options: &DecodeOptions) -> Vec<u8> {
+    let format = options.format_for(header);
+    if format == PixelFormat::from_channels(header.channels) && !options.premultiplied {
+        return pixels.to_vec();
+    }
+    let n_pixels = pixels.len() / header.channels as usize;
+    let mut out = vec![0; n_pixels * format.bytes_per_pixel()];
+    for (pixel, out) in pixels
+        .chunks_exact(header.channels as usize)
+        .zip(out.chunks_exact_mut(format.bytes_per_pixel()))
+    {
+        let pixel = Pixel::from(pixel);
+        let pixel = if options.premultiplied {
+            pixel.premultiplied()
+        } else {
+            pixel
+        };
+        format.write(pixel, out);
+    }
+    out
+}
+
+/// Whether `data` starts like a sequence rather than a plain QOI file.
+pub fn is_sequence(data: &[u8]) -> bool {
+    data.starts_with(&SEQUENCE_MAGIC)
+}
@@ -0,0 +1,131 @@
+//! Scans ahead for runs of identical pixels, comparing them as packed `u32`s.
+//!
+//! Which loop runs is decided when compiling for the target, with a single check at runtime:
+//! - x86_64 with `std`: AVX2 if the CPU reports it, otherwise SSE2.
+//! - x86_64 without `std`: always SSE2, which every x86_64 CPU has, since there is no runtime
+//!   detection. Building with `-C target-feature=+avx2` does not change this.
+//! - Every other target: always the portable scalar loop. A binary for such a target never
+//!   picks a vector loop, even on a CPU that has one.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// The number of `N`-byte pixels at the start of `data` that are equal to `pixel`.
+#[inline]
+pub(crate) fn run_length<const N: usize>(data: &[u8], pixel: &[u8]) -> usize {
+    if N == 4 {
+        run_length_u32(data, u32::from_le_bytes(pixel.try_into().unwrap()))
+    } else {
+        data.chunks_exact(N).take_while(|&p| p == pixel).count()
+    }
+}
+
+fn run_length_u32(data: &[u8], pixel: u32) -> usize {
+    // The only check made at runtime, the rest is fixed by the `cfg`s.
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { run_length_avx2(data, pixel) };
+    }
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: SSE2 is part of the x86_64 baseline.
+    return unsafe { run_length_sse2(data, pixel) };
+    #[cfg(not(target_arch = "x86_64"))]
+    run_length_scalar(data, pixel)
+}
+
+fn run_length_scalar(data: &[u8], pixel: u32) -> usize {
+    data.chunks_exact(4)
+        .take_while(|p| u32::from_le_bytes((*p).try_into().unwrap()) == pixel)
+        .count()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn run_length_sse2(data: &[u8], pixel: u32) -> usize {
+    let needle = _mm_set1_epi32(pixel as i32);
+    let mut i = 0;
+    while i + 16 <= data.len() {
+        // SAFETY: the 16 bytes at `i` are in bounds, and the load may be unaligned.
+        let block = unsafe { _mm_loadu_si128(data.as_ptr().add(i).cast()) };
+        let mask = _mm_movemask_epi8(_mm_cmpeq_epi32(block, needle)) as u32;
+        if mask != 0xffff {
+            return (i + mask.trailing_ones() as usize) / 4;
+        }
+        i += 16;
+    }
+    i / 4 + run_length_scalar(&data[i..], pixel)
+}
+
+// Without `std` there is no runtime detection, so only the baseline is used.
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+#[target_feature(enable = "avx2")]
+unsafe fn run_length_avx2(data: &[u8], pixel: u32) -> usize {
+    let needle = _mm256_set1_epi32(pixel as i32);
+    let mut i = 0;
+    while i + 32 <= data.len() {
+        // SAFETY: the 32 bytes at `i` are in bounds, and the load may be unaligned.
+        let block = unsafe { _mm256_loadu_si256(data.as_ptr().add(i).cast()) };
+        let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi32(block, needle)) as u32;
+        if mask != u32::MAX {
+            return (i + mask.trailing_ones() as usize) / 4;
+        }
+        i += 32;
+    }
+    i / 4 + run_length_scalar(&data[i..], pixel)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// Every run length up to a few blocks, broken by a pixel that differs in a single byte, so
+    /// the mismatch lands on every position within a block.
+    fn cases() -> impl Iterator<Item = (Vec<u8>, u32, usize)> {
+        let pixel = 0x8040_2010_u32;
+        (0..80).flat_map(move |len| {
+            (0..4).map(move |byte| {
+                let mut data = pixel.to_le_bytes().repeat(len);
+                let mut other = pixel.to_le_bytes();
+                other[byte] ^= 1;
+                data.extend_from_slice(&other);
+                data.extend_from_slice(&pixel.to_le_bytes().repeat(byte * 3));
+                (data, pixel, len)
+            })
+        })
+    }
+
+    #[test]
+    fn test_run_length_scalar() {
+        for (data, pixel, len) in cases() {
+            assert_eq!(run_length_scalar(&data, pixel), len);
+            assert_eq!(run_length_scalar(&data[..len * 4], pixel), len);
+        }
+    }
+
+    #[test]
+    fn test_run_length_matches_scalar() {
+        for (data, pixel, len) in cases() {
+            for end in [data.len(), len * 4, len * 4 + 2] {
+                let data = &data[..end];
+                let expected = run_length_scalar(data, pixel);
+                assert_eq!(run_length::<4>(data, &pixel.to_le_bytes()), expected);
+                #[cfg(target_arch = "x86_64")]
+                {
+                    assert_eq!(unsafe { run_length_sse2(data, pixel) }, expected);
+                    if std::is_x86_feature_detected!("avx2") {
+                        assert_eq!(unsafe { run_length_avx2(data, pixel) }, expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_length_rgb() {
+        let data = [1, 2, 3, 1, 2, 3, 1, 2, 4, 1, 2, 3];
+        assert_eq!(run_length::<3>(&data, &[1, 2, 3]), 2);
+        assert_eq!(run_length::<3>(&data[6..], &[1, 2, 3]), 0);
+        assert_eq!(run_length::<3>(&data[..5], &[1, 2, 3]), 1);
+    }
+}
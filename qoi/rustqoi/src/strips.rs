@@ -0,0 +1,282 @@
+//! A container that splits an image into horizontal strips, each an independent QOI file, so
+//! they can be encoded and decoded in parallel.
+//!
+//! ```text
+//! magic          4 bytes   "qois"
+//! header         14 bytes  the QOI header of the whole image
+//! rows           u32 BE    rows per strip, the last strip may have fewer
+//! count          u32 BE    the number of strips, height / rows rounded up
+//! offsets        count * u64 BE, where each strip starts counted from the start of the file
+//! strips         count complete QOI files, header and end marker included
+//! ```
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    header::QoiHeader, options::DecodeOptions, Decoder, Encoder, QoiError, QOI_HEADER_SIZE,
+};
+
+pub(crate) const STRIPS_MAGIC: [u8; 4] = *b"qois";
+
+/// The rows per strip [`StripEncoder::new`] picks.
+pub const DEFAULT_STRIP_ROWS: u32 = 64;
+
+const PREFIX_SIZE: usize = 4 + QOI_HEADER_SIZE + 4 + 4;
+
+/// Encodes tightly packed RGB or RGBA pixels into the strip container.
+pub struct StripEncoder {
+    header: QoiHeader,
+    rows_per_strip: u32,
+}
+
+impl StripEncoder {
+    pub fn new(header: QoiHeader) -> Self {
+        StripEncoder {
+            header,
+            rows_per_strip: DEFAULT_STRIP_ROWS,
+        }
+    }
+
+    /// Sets the number of rows in each strip. Fewer rows mean more parallelism but a worse
+    /// compression ratio, since every strip starts without a previous pixel and index.
+    pub fn with_rows_per_strip(mut self, rows_per_strip: u32) -> Self {
+        self.rows_per_strip = rows_per_strip.max(1);
+        self
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// Encodes `data`, which holds `header.channels` bytes per pixel.
+    pub fn encode(&self, data: &(impl AsRef<[u8]> + ?Sized)) -> Result<Vec<u8>, QoiError> {
+        let data = data.as_ref();
+        self.header.validate()?;
+        let expected = self.header.decoded_size().ok_or(QoiError::LimitExceeded)?;
+        if data.len() != expected {
+            return Err(QoiError::DataSizeMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let rows = self.rows_per_strip as usize;
+        // Exact, since the size matched. An image without rows has no strips to cut.
+        let row_bytes = data.len() / (self.header.height as usize).max(1);
+        let strips: Vec<(QoiHeader, &[u8])> = (0..self.header.height as usize)
+            .step_by(rows)
+            .map(|first_row| {
+                let height = rows.min(self.header.height as usize - first_row);
+                let header = QoiHeader {
+                    height: height as u32,
+                    ..self.header
+                };
+                let start = first_row * row_bytes;
+                (header, &data[start..start + height * row_bytes])
+            })
+            .collect();
+        let strips = try_map(strips, |(header, data)| Encoder::new(header).encode(data))?;
+
+        let table_size = PREFIX_SIZE + 8 * strips.len();
+        let total = table_size + strips.iter().map(Vec::len).sum::<usize>();
+        let mut out = Vec::with_capacity(total);
+        out.extend_from_slice(&STRIPS_MAGIC);
+        out.extend_from_slice(&self.header.encode());
+        out.extend_from_slice(&self.rows_per_strip.to_be_bytes());
+        out.extend_from_slice(&(strips.len() as u32).to_be_bytes());
+        let mut offset = table_size;
+        for strip in &strips {
+            out.extend_from_slice(&(offset as u64).to_be_bytes());
+            offset += strip.len();
+        }
+        for strip in &strips {
+            out.extend_from_slice(strip);
+        }
+        Ok(out)
+    }
+}
+
+/// Decodes an image held in the strip container.
+pub struct StripDecoder<'a> {
+    header: QoiHeader,
+    rows_per_strip: u32,
+    strips: Vec<&'a [u8]>,
+    options: DecodeOptions,
+    decoded_size: usize,
+}
+
+impl<'a> StripDecoder<'a> {
+    /// Parses the container header and offset table.
+    pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self, QoiError> {
+        StripDecoder::with_options(data, DecodeOptions::default())
+    }
+
+    /// Like [`StripDecoder::new`], but applies `options` to the whole image and to every
+    /// strip.
+    pub fn with_options(
+        data: &'a (impl AsRef<[u8]> + ?Sized),
+        options: DecodeOptions,
+    ) -> Result<Self, QoiError> {
+        let data = data.as_ref();
+        if data.len() < PREFIX_SIZE {
+            return Err(QoiError::TooShort);
+        }
+        if data[..4] != STRIPS_MAGIC {
+            return Err(QoiError::BadMagic);
+        }
+        let header = QoiHeader::try_decode(&data[4..4 + QOI_HEADER_SIZE])?;
+        let decoded_size = options.check(&header)?;
+        let read_u32 = |at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+        let rows_per_strip = read_u32(PREFIX_SIZE - 8);
+        let count = read_u32(PREFIX_SIZE - 4) as usize;
+        if rows_per_strip == 0 || count != header.height.div_ceil(rows_per_strip) as usize {
+            return Err(QoiError::BadStripTable);
+        }
+
+        let table = count
+            .checked_mul(8)
+            .and_then(|len| data[PREFIX_SIZE..].get(..len))
+            .ok_or(QoiError::TooShort)?;
+        let mut offsets = table
+            .chunks_exact(8)
+            .map(|offset| usize::try_from(u64::from_be_bytes(offset.try_into().unwrap())))
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| QoiError::BadStripTable)?;
+        offsets.push(data.len());
+        if offsets
+            .first()
+            .is_some_and(|&first| first != PREFIX_SIZE + table.len())
+            || offsets.windows(2).any(|pair| pair[0] > pair[1])
+        {
+            return Err(QoiError::BadStripTable);
+        }
+        let strips = offsets
+            .windows(2)
+            .map(|pair| &data[pair[0]..pair[1]])
+            .collect();
+        Ok(StripDecoder {
+            header,
+            rows_per_strip,
+            strips,
+            options,
+            decoded_size,
+        })
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    pub fn rows_per_strip(&self) -> u32 {
+        self.rows_per_strip
+    }
+
+    /// The strips, each a complete QOI file.
+    pub fn strips(&self) -> &[&'a [u8]] {
+        &self.strips
+    }
+
+    /// The size in bytes of the decoded pixels.
+    pub fn decoded_size(&self) -> usize {
+        self.decoded_size
+    }
+
+    /// Decodes all strips into a tightly packed buffer in the layout picked by the options.
+    pub fn decode(&self) -> Result<Vec<u8>, QoiError> {
+        let mut out = alloc::vec![0; self.decoded_size];
+        self.decode_into(&mut out)?;
+        Ok(out)
+    }
+
+    /// Like [`StripDecoder::decode`], but writes the pixels to the start of `out`, which must
+    /// hold at least [`StripDecoder::decoded_size`] bytes.
+    pub fn decode_into(&self, out: &mut [u8]) -> Result<(), QoiError> {
+        let out = out
+            .get_mut(..self.decoded_size)
+            .ok_or(QoiError::BufferTooSmall)?;
+        // The container may claim more rows per strip than the image has, but a strip never
+        // holds more than all of them, so this stays within the decoded size.
+        let rows = self.rows_per_strip.min(self.header.height);
+        let row_bytes = self
+            .decoded_size
+            .checked_div(self.header.height as usize)
+            .unwrap_or(0);
+        let strip_bytes = row_bytes
+            .checked_mul(rows as usize)
+            .ok_or(QoiError::BadStripTable)?;
+        // An image without pixels still has its strips checked, each against no output.
+        let outs: Vec<&mut [u8]> = if strip_bytes == 0 {
+            self.strips.iter().map(|_| &mut [][..]).collect()
+        } else {
+            out.chunks_mut(strip_bytes).collect()
+        };
+        let strips: Vec<_> = self.strips.iter().copied().zip(outs).enumerate().collect();
+        try_map(strips, |(i, (strip, out))| {
+            let decoder = Decoder::with_options(strip, self.options)?;
+            // The table holds one strip per `rows` rows, so `i * rows` is below the height.
+            let expected = QoiHeader {
+                height: rows.min(self.header.height - i as u32 * rows),
+                ..self.header
+            };
+            if *decoder.header() != expected || decoder.decoded_size() != out.len() {
+                return Err(QoiError::BadStripTable);
+            }
+            decoder.decode_into(out)
+        })?;
+        Ok(())
+    }
+
+    /// Re-encodes the image as a single plain QOI file, for readers that do not know the
+    /// container.
+    pub fn to_qoi(&self) -> Result<Vec<u8>, QoiError> {
+        let native = StripDecoder {
+            options: DecodeOptions {
+                format: None,
+                premultiplied: false,
+                ..self.options
+            },
+            strips: self.strips.clone(),
+            decoded_size: self.header.decoded_size().ok_or(QoiError::LimitExceeded)?,
+            ..*self
+        };
+        Encoder::new(self.header).encode(&native.decode()?)
+    }
+}
+
+/// Whether `data` starts like the strip container rather than a plain QOI file.
+pub fn is_strips(data: &[u8]) -> bool {
+    data.starts_with(&STRIPS_MAGIC)
+}
+
+/// Encodes tightly packed RGB or RGBA pixels into the strip container with
+/// [`DEFAULT_STRIP_ROWS`] rows per strip.
+pub fn encode_strips(
+    data: &(impl AsRef<[u8]> + ?Sized),
+    header: QoiHeader,
+) -> Result<Vec<u8>, QoiError> {
+    StripEncoder::new(header).encode(data)
+}
+
+/// Decodes the strip container into its header and a tightly packed RGB or RGBA buffer.
+pub fn decode_strips(data: &(impl AsRef<[u8]> + ?Sized)) -> Result<(QoiHeader, Vec<u8>), QoiError> {
+    let decoder = StripDecoder::new(data)?;
+    let out = decoder.decode()?;
+    Ok((decoder.header, out))
+}
+
+/// Applies `f` to every strip, on the rayon thread pool when the `rayon` feature is enabled.
+#[cfg(feature = "rayon")]
+fn try_map<T: Send, U: Send>(
+    items: Vec<T>,
+    f: impl Fn(T) -> Result<U, QoiError> + Sync + Send,
+) -> Result<Vec<U>, QoiError> {
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn try_map<T, U>(items: Vec<T>, f: impl Fn(T) -> Result<U, QoiError>) -> Result<Vec<U>, QoiError> {
+    items.into_iter().map(f).collect()
+}
@@ -0,0 +1,285 @@
+//! Inter-frame coding for streams of similar frames, such as screen captures.
+//!
+//! The first frame is a plain QOI file, a key frame. Every frame after it may instead be a delta
+//! frame, which has the QOI header and end marker but starts with `qoid` and predicts every
+//! pixel from the same position in the frame before it rather than from the pixel to its left:
+//!
+//! ```text
+//! 0b00xxxxxx  index    as in QOI
+//! 0b01xxxxxx  diff     as in QOI, relative to the pixel of the previous frame
+//! 0b10xxxxxx  luma     as in QOI, relative to the pixel of the previous frame
+//! 0xc0..0xfc  skip     1 to 61 pixels unchanged from the previous frame
+//! 0xfd        skip     62 pixels plus the count in the LEB128 number that follows
+//! 0xfe        rgb      as in QOI, alpha from the pixel of the previous frame
+//! 0xff        rgba     as in QOI
+//! ```
+//!
+//! The index holds every pixel written by an op other than a skip.
+
+use alloc::vec::Vec;
+
+use crate::{
+    format::PixelFormat,
+    header::QoiHeader,
+    options::DecodeOptions,
+    pixel::{Pixel, Runner},
+    Decoder, Encoder, QoiError, QOI_END_MARKER, QOI_FOOTER_SIZE, QOI_HEADER_SIZE, QOI_MAGIC,
+    QOI_OP_DIFF, QOI_OP_DIFF_END, QOI_OP_INDEX, QOI_OP_INDEX_END, QOI_OP_LUMA, QOI_OP_LUMA_END,
+    QOI_OP_RGB, QOI_OP_RGBA, QOI_OP_RUN,
+};
+
+pub(crate) const DELTA_MAGIC: [u8; 4] = *b"qoid";
+
+const OP_SKIP_END: u8 = QOI_OP_RUN | 60;
+const OP_LONG_SKIP: u8 = QOI_OP_RUN | 61;
+/// The longest skip a single byte holds.
+const MAX_SHORT_SKIP: usize = 61;
+
+/// Encodes a stream of frames, each as a delta against the one before it.
+pub struct TemporalEncoder {
+    header: QoiHeader,
+    reference: Option<Vec<u8>>,
+}
+
+impl TemporalEncoder {
+    pub fn new(header: QoiHeader) -> Self {
+        TemporalEncoder {
+            header,
+            reference: None,
+        }
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// Makes the next frame a key frame, which decodes without the frames before it.
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+
+    /// Encodes `data`, which holds `header.channels` bytes per pixel. The first frame and the
+    /// first after [`TemporalEncoder::reset`] become plain QOI files, all others delta frames.
+    pub fn encode_frame(&mut self, data: &[u8]) -> Result<Vec<u8>, QoiError> {
+        self.header.validate()?;
+        let expected = self.header.decoded_size().ok_or(QoiError::LimitExceeded)?;
+        if data.len() != expected {
+            return Err(QoiError::DataSizeMismatch {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let encoded = match &self.reference {
+            Some(reference) => encode_delta(&self.header, reference, data),
+            None => Encoder::new(self.header).encode(data)?,
+        };
+        let reference = self.reference.get_or_insert_with(Vec::new);
+        reference.clear();
+        reference.extend_from_slice(data);
+        Ok(encoded)
+    }
+}
+
+fn encode_delta(header: &QoiHeader, reference: &[u8], data: &[u8]) -> Vec<u8> {
+    let channels = header.channels as usize;
+    let mut out = Vec::with_capacity(QOI_HEADER_SIZE + data.len() / 16 + QOI_FOOTER_SIZE);
+    out.extend_from_slice(&DELTA_MAGIC);
+    out.extend_from_slice(&header.encode()[4..]);
+
+    let mut runner = Runner::new();
+    let mut skip = 0;
+    for (raw, predicted) in data
+        .chunks_exact(channels)
+        .zip(reference.chunks_exact(channels))
+    {
+        if raw == predicted {
+            skip += 1;
+            continue;
+        }
+        put_skip(skip, &mut out);
+        skip = 0;
+
+        let pixel = Pixel::from(raw);
+        if let Some(ix) = runner.match_or_update(&pixel) {
+            out.push(QOI_OP_INDEX | ix);
+            continue;
+        }
+        let raw_diff = pixel - Pixel::from(predicted);
+        if let Some(diff) = raw_diff.diff_offset() {
+            out.push(diff);
+        } else if let Some(luma_diff) = raw_diff.luma_diff_offset() {
+            out.extend_from_slice(&[luma_diff.0, luma_diff.1]);
+        } else if raw_diff.a == 0 {
+            out.extend_from_slice(&[QOI_OP_RGB, pixel.r, pixel.g, pixel.b]);
+        } else {
+            out.extend_from_slice(&[QOI_OP_RGBA, pixel.r, pixel.g, pixel.b, pixel.a]);
+        }
+    }
+    put_skip(skip, &mut out);
+    out.extend_from_slice(&QOI_END_MARKER);
+    out
+}
+
+fn put_skip(skip: usize, out: &mut Vec<u8>) {
+    match skip {
+        0 => {}
+        1..=MAX_SHORT_SKIP => out.push(QOI_OP_RUN | (skip - 1) as u8),
+        _ => {
+            out.push(OP_LONG_SKIP);
+            let mut rest = skip - (MAX_SHORT_SKIP + 1);
+            while rest >= 0x80 {
+                out.push(rest as u8 | 0x80);
+                rest >>= 7;
+            }
+            out.push(rest as u8);
+        }
+    }
+}
+
+/// Decodes a stream of key and delta frames, keeping the last frame as the reference for the
+/// next one.
+///
+/// Frames are always decoded in the RGB or RGBA layout of the file, since that is what the next
+/// frame builds on.
+pub struct TemporalDecoder {
+    options: DecodeOptions,
+    /// The header of the last frame, `None` before the first key frame or after an error.
+    header: Option<QoiHeader>,
+    frame: Vec<u8>,
+}
+
+impl TemporalDecoder {
+    pub fn new() -> Self {
+        TemporalDecoder::with_options(DecodeOptions::default())
+    }
+
+    /// Like [`TemporalDecoder::new`], but applies the limits in `options` to every frame and
+    /// its strictness to key frames. Delta frames are always decoded strictly.
+    pub fn with_options(options: DecodeOptions) -> Self {
+        TemporalDecoder {
+            options: DecodeOptions {
+                format: None,
+                premultiplied: false,
+                ..options
+            },
+            header: None,
+            frame: Vec::new(),
+        }
+    }
+
+    /// Decodes a key or delta frame and returns its header and pixels.
+    pub fn decode_frame(&mut self, data: &[u8]) -> Result<(QoiHeader, &[u8]), QoiError> {
+        let header = self.header.take();
+        let header = if is_delta(data) {
+            self.decode_delta(data, header)?
+        } else {
+            let decoder = Decoder::with_options(data, self.options)?;
+            self.frame.resize(decoder.decoded_size(), 0);
+            decoder.decode_into(&mut self.frame)?;
+            *decoder.header()
+        };
+        self.header = Some(header);
+        Ok((header, &self.frame))
+    }
+
+    /// Decodes the delta frame in place over the previous frame.
+    fn decode_delta(
+        &mut self,
+        data: &[u8],
+        reference: Option<QoiHeader>,
+    ) -> Result<QoiHeader, QoiError> {
+        if data.len() < QOI_HEADER_SIZE + QOI_FOOTER_SIZE {
+            return Err(QoiError::TooShort);
+        }
+        let mut header_bytes = [0; QOI_HEADER_SIZE];
+        header_bytes.copy_from_slice(&data[..QOI_HEADER_SIZE]);
+        header_bytes[..4].copy_from_slice(&QOI_MAGIC);
+        let header = QoiHeader::try_decode(&header_bytes)?;
+        self.options.check(&header)?;
+        if reference != Some(header) {
+            return Err(QoiError::MissingReference);
+        }
+        let mut body = data[QOI_HEADER_SIZE..]
+            .strip_suffix(&QOI_END_MARKER)
+            .ok_or(QoiError::BadFooter)?;
+
+        let channels = header.channels as usize;
+        let format = PixelFormat::from_channels(header.channels);
+        let mut runner = Runner::new();
+        let mut pixels = self.frame.chunks_exact_mut(channels);
+        while let [op, tail @ ..] = body {
+            let skip = match *op {
+                QOI_OP_RUN..=OP_SKIP_END => Some((usize::from(op & !QOI_OP_RUN) + 1, tail)),
+                OP_LONG_SKIP => {
+                    let (count, tail) = read_leb128(tail)?;
+                    Some((count.saturating_add(MAX_SHORT_SKIP + 1), tail))
+                }
+                _ => None,
+            };
+            if let Some((skip, tail)) = skip {
+                if skip > pixels.len() {
+                    return Err(QoiError::TooManyPixels);
+                }
+                pixels.nth(skip - 1);
+                body = tail;
+                continue;
+            }
+
+            let out = pixels.next().ok_or(QoiError::TooManyPixels)?;
+            let predicted = Pixel::from(&*out);
+            let (pixel, tail) = match body {
+                [QOI_OP_RGB, r, g, b, tail @ ..] => (predicted.update_rgb(*r, *g, *b), tail),
+                [QOI_OP_RGBA, r, g, b, a, tail @ ..] => {
+                    (predicted.update_rgba(*r, *g, *b, *a), tail)
+                }
+                [byte @ QOI_OP_INDEX..=QOI_OP_INDEX_END, tail @ ..] => {
+                    (runner.memory[*byte as usize], tail)
+                }
+                [byte @ QOI_OP_DIFF..=QOI_OP_DIFF_END, tail @ ..] => {
+                    (predicted.decode_diff(*byte & !QOI_OP_RUN), tail)
+                }
+                [byte_1 @ QOI_OP_LUMA..=QOI_OP_LUMA_END, byte_2, tail @ ..] => (
+                    predicted.decode_luma_diff(*byte_1 & !QOI_OP_RUN, *byte_2),
+                    tail,
+                ),
+                _ => return Err(QoiError::TruncatedOp),
+            };
+            if *op > QOI_OP_INDEX_END {
+                runner.update(pixel);
+            }
+            format.write(pixel, out);
+            body = tail;
+        }
+        if pixels.len() != 0 {
+            return Err(QoiError::TooFewPixels);
+        }
+        Ok(header)
+    }
+}
+
+impl Default for TemporalDecoder {
+    fn default() -> Self {
+        TemporalDecoder::new()
+    }
+}
+
+/// Reads an unsigned LEB128 number. One too large for `usize` cannot be a pixel count.
+fn read_leb128(data: &[u8]) -> Result<(usize, &[u8]), QoiError> {
+    let mut value = 0usize;
+    for (i, &byte) in data.iter().enumerate() {
+        value = 1usize
+            .checked_shl(7 * i as u32)
+            .and_then(|scale| usize::from(byte & 0x7f).checked_mul(scale))
+            .and_then(|bits| value.checked_add(bits))
+            .ok_or(QoiError::TooManyPixels)?;
+        if byte & 0x80 == 0 {
+            return Ok((value, &data[i + 1..]));
+        }
+    }
+    Err(QoiError::TruncatedOp)
+}
+
+/// Whether `data` starts like a delta frame rather than a plain QOI file.
+pub fn is_delta(data: &[u8]) -> bool {
+    data.starts_with(&DELTA_MAGIC)
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use crate::{encoder::EncoderState, header::QoiHeader, pixel::Pixel, QoiError, QOI_END_MARKER};
+
+/// Encodes a QOI stream incrementally into a writer.
+///
+/// The header is written up front, pixels are passed in through [`QoiWriter::write_row`] in
+/// chunks of any number of whole pixels, and [`QoiWriter::finish`] writes the end marker.
+pub struct QoiWriter<W: Write> {
+    writer: W,
+    header: QoiHeader,
+    state: EncoderState,
+    pixels_left: usize,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> QoiWriter<W> {
+    /// Validates and writes the header. Nothing is written for a header that is rejected.
+    pub fn new(mut writer: W, header: QoiHeader) -> Result<Self, QoiError> {
+        header.validate()?;
+        let pixels_left = header.n_pixels().ok_or(QoiError::LimitExceeded)?;
+        writer.write_all(&header.encode())?;
+        Ok(QoiWriter {
+            writer,
+            header,
+            state: EncoderState::new(),
+            pixels_left,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// The number of pixels that still have to be written before [`QoiWriter::finish`].
+    pub fn pixels_left(&self) -> usize {
+        self.pixels_left
+    }
+
+    /// Encodes `data`, which holds `header.channels` bytes per pixel. Need not be a whole row.
+    pub fn write_row(&mut self, data: &[u8]) -> Result<(), QoiError> {
+        let channels = self.header.channels as usize;
+        if !data.len().is_multiple_of(channels) {
+            return Err(QoiError::DataSizeMismatch {
+                expected: data.len().next_multiple_of(channels),
+                actual: data.len(),
+            });
+        }
+        if data.len() / channels > self.pixels_left {
+            return Err(QoiError::TooManyPixels);
+        }
+        self.pixels_left -= data.len() / channels;
+
+        self.buf.clear();
+        for pixel in data.chunks_exact(channels).map(Pixel::from) {
+            self.state.push(pixel, &mut self.buf);
+        }
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Writes the pending run and the end marker and hands back the writer.
+    pub fn finish(mut self) -> Result<W, QoiError> {
+        if self.pixels_left != 0 {
+            return Err(QoiError::TooFewPixels);
+        }
+        self.buf.clear();
+        self.state.flush_run(&mut self.buf);
+        self.buf.extend_from_slice(&QOI_END_MARKER);
+        self.writer.write_all(&self.buf)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
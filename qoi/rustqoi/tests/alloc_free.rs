@@ -0,0 +1,50 @@
+//! Counts the allocations made by the buffer-reusing APIs. Kept in its own test binary because
+//! the counting allocator is global.
+use rustqoi::{decode_into, encode_into, encode_to_slice, max_encoded_size, ColorSpace, QoiHeader};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[test]
+fn test_frame_loop_does_not_allocate() {
+    let header = QoiHeader::new(64, 64, 4, ColorSpace::Srgb);
+    let frames: Vec<Vec<u8>> = (0..4u8)
+        .map(|frame| {
+            (0..64 * 64)
+                .flat_map(|i: u32| [(i % 64) as u8, (i / 64) as u8, frame * 40, 255])
+                .collect()
+        })
+        .collect();
+    let mut encoded = Vec::with_capacity(max_encoded_size(64, 64, 4));
+    let mut slice = vec![0; max_encoded_size(64, 64, 4)];
+    let mut decoded = vec![0; header.decoded_size().unwrap()];
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for frame in &frames {
+        encoded.clear();
+        encode_into(frame, header, &mut encoded).unwrap();
+        let len = encode_to_slice(frame, header, &mut slice).unwrap();
+        decode_into(&slice[..len], &mut decoded).unwrap();
+        assert!(decoded == *frame);
+    }
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), before);
+}
@@ -0,0 +1,894 @@
+mod common;
+
+use common::Rng;
+use image::io::Reader as ImageReader;
+use rustqoi::{
+    decode, decode_into, encode, encode_into, encode_to_slice, max_encoded_size, probe,
+    read_header, ColorSpace, DecodeOptions, Decoder, Encoder, PixelFormat, PushDecoder, QoiError,
+    QoiHeader, QoiReader, SourceLayout,
+};
+use std::{fs::File, io::Read};
+
+const QOI_OP_RUN: u8 = 0b11000000;
+
+#[test]
+fn test_encode_2x2() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let image = [[white, black], [black, white]].concat().concat();
+
+    let expected = vec![
+        113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 2, 4, 1, 85, 127, 192, 38, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    assert_eq!(encoded, Ok(expected));
+}
+#[test]
+fn test_encode_162_run() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let len = 162;
+    let image = black.repeat(len);
+
+    let expected = vec![
+        113,
+        111,
+        105,
+        102,
+        0,
+        0,
+        0,
+        len as u8,
+        0,
+        0,
+        0,
+        1,
+        4,
+        1,
+        QOI_OP_RUN | 61,
+        QOI_OP_RUN | 61,
+        QOI_OP_RUN | 37,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        1,
+    ];
+
+    let encoded = Encoder::new(QoiHeader::new(len as u32, 1, 4, ColorSpace::Linear)).encode(&image);
+    assert_eq!(encoded, Ok(expected));
+}
+#[test]
+fn test_decode_162_run() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let len = 162;
+    let image = black.repeat(len);
+
+    let encoded = vec![
+        113,
+        111,
+        105,
+        102,
+        0,
+        0,
+        0,
+        len as u8,
+        0,
+        0,
+        0,
+        1,
+        4,
+        1,
+        QOI_OP_RUN | 61,
+        QOI_OP_RUN | 61,
+        QOI_OP_RUN | 37,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        1,
+    ];
+    let decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(
+        decoder.header(),
+        &QoiHeader::new(len as u32, 1, 4, ColorSpace::Linear)
+    );
+    assert_eq!(decoder.decode(), Ok(image));
+}
+
+#[test]
+fn test_encode_empty() {
+    let empty_image_encoded = vec![
+        113, 111, 105, 102, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    let encoded = encode(&[], QoiHeader::new(0, 0, 4, ColorSpace::Linear));
+    assert_eq!(encoded, Ok(empty_image_encoded));
+}
+
+#[test]
+fn test_decode_empty() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    let decoded = decode(&data);
+    assert_eq!(
+        decoded,
+        Ok((QoiHeader::new(0, 0, 4, ColorSpace::Linear), vec![]))
+    );
+}
+
+#[test]
+fn test_decode_2x2() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 2, 4, 1, 85, 127, 192, 38, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let expected = [[white, black], [black, white]].concat().concat();
+
+    let decoded = decode(&data);
+    assert_eq!(
+        decoded,
+        Ok((QoiHeader::new(2, 2, 4, ColorSpace::Linear), expected))
+    );
+}
+
+#[test]
+fn test_encode_decode_empty() {
+    let encoded = encode(&[], QoiHeader::new(0, 0, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, vec![]);
+}
+
+#[test]
+fn test_encode_decode_2x2() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let image = [[white, black], [black, white]].concat().concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+
+#[test]
+fn test_encode_decode_2x2_luma() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let grey: [u8; 4] = [9, 10, 11, 255];
+    let image = [[white, black], [grey, white]].concat().concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+
+#[test]
+fn test_encode_decode_2x2_rgb() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let red: [u8; 4] = [155, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let grey: [u8; 4] = [10, 10, 10, 255];
+    let image = [[red, black], [grey, white]].concat().concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+#[test]
+fn test_encode_decode_2x2_run() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let image = [[black, black], [white, white]].concat().concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+#[test]
+fn test_encode_decode_2x2_alpha() {
+    let black: [u8; 4] = [0, 0, 0, 255];
+    let white: [u8; 4] = [255, 255, 255, 255];
+    let transparent: [u8; 4] = [0, 0, 0, 0];
+    let image = [[white, black], [transparent, white]].concat().concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 4, ColorSpace::Linear));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+
+#[test]
+fn test_encode_decode_go() {
+    let img = ImageReader::open("../go.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_rgba8();
+    let data = img.to_vec();
+    let header = QoiHeader::new(img.width(), img.height(), 4, ColorSpace::Linear);
+    let encoded = encode(&data, header).unwrap();
+
+    // let mut file = File::create("../go.qoi").unwrap();
+    // file.write_all(&encoded).unwrap();
+
+    let decoded = decode(&encoded).unwrap();
+    assert_eq!(decoded.0, header);
+    assert_eq!(decoded.1.len(), data.len());
+    println!(
+        "{:?}",
+        decoded
+            .1
+            .chunks_exact(4)
+            .zip(data.chunks_exact(4))
+            .enumerate()
+            .find(|(_, (l, r))| l != r)
+    );
+    assert!(decoded.1.iter().eq(data.iter()), "data not the same");
+}
+
+#[test]
+fn test_decode_go() {
+    let encoded = {
+        let mut buf = Vec::with_capacity(1_000_000);
+        let _img = File::open("../go.qoi").unwrap().read_to_end(&mut buf);
+        buf
+    };
+    let _decoded = decode(&encoded).unwrap();
+}
+
+#[test]
+fn test_decode_bad_magic() {
+    let data = vec![
+        113, 111, 105, 103, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    assert_eq!(decode(&data), Err(QoiError::BadMagic));
+}
+
+#[test]
+fn test_decode_too_short() {
+    assert_eq!(decode(&[113, 111, 105, 102]), Err(QoiError::TooShort));
+}
+
+#[test]
+fn test_decode_invalid_header_bytes() {
+    let mut data = vec![
+        113, 111, 105, 102, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    data[12] = 1;
+    assert_eq!(decode(&data), Err(QoiError::InvalidChannels(1)));
+    data[12] = 3;
+    data[13] = 2;
+    assert_eq!(decode(&data), Err(QoiError::InvalidColorspace(2)));
+}
+
+#[test]
+fn test_decode_truncated_rgba() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 1, 0, 0, 0, 1, 4, 1, 255, 10, 20, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    assert_eq!(decode(&data), Err(QoiError::TruncatedOp));
+}
+
+#[test]
+fn test_decode_pixel_count_mismatch() {
+    let mut data = vec![
+        113,
+        111,
+        105,
+        102,
+        0,
+        0,
+        0,
+        2,
+        0,
+        0,
+        0,
+        1,
+        4,
+        1,
+        QOI_OP_RUN | 2,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        1,
+    ];
+    assert_eq!(decode(&data), Err(QoiError::TooManyPixels));
+    data[7] = 4;
+    assert_eq!(decode(&data), Err(QoiError::TooFewPixels));
+}
+
+#[test]
+fn test_decode_index_holds_decoded_pixels() {
+    // hash(10, 20, 30, 255) is 9, hash(0, 0, 0, 255) is 53.
+    for (ops, expected) in [
+        (vec![0xfe, 10, 20, 30, 9], [10, 20, 30, 255].repeat(2)),
+        (vec![QOI_OP_RUN, 53], [0, 0, 0, 255].repeat(2)),
+    ] {
+        let mut data = vec![113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 1, 4, 0];
+        data.extend_from_slice(&ops);
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(decode(&data).unwrap().1, expected);
+
+        let mut decoder = PushDecoder::new();
+        decoder.feed(&data).unwrap();
+        assert_eq!(decoder.drain_pixels().collect::<Vec<_>>(), expected);
+
+        let mut reader = QoiReader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.read_row().unwrap(), Some(expected.as_slice()));
+    }
+}
+
+#[test]
+fn test_decode_bad_footer() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    assert_eq!(decode(&data), Err(QoiError::BadFooter));
+}
+
+#[test]
+fn test_encode_data_size_mismatch() {
+    let encoded = encode(&[0, 0, 0, 255], QoiHeader::new(2, 1, 4, ColorSpace::Linear));
+    assert_eq!(
+        encoded,
+        Err(QoiError::DataSizeMismatch {
+            expected: 8,
+            actual: 4
+        })
+    );
+
+    // Checked before any buffer is sized from the header.
+    for side in [1 << 20, u32::MAX] {
+        let header = QoiHeader::new(side, side, 4, ColorSpace::Srgb);
+        assert!(matches!(
+            encode(&[0; 4], header),
+            Err(QoiError::DataSizeMismatch { actual: 4, .. })
+        ));
+    }
+}
+
+#[test]
+fn test_encode_2x2_rgb_channels() {
+    let black: [u8; 3] = [0, 0, 0];
+    let white: [u8; 3] = [255, 255, 255];
+    let image = [[white, black], [black, white]].concat().concat();
+
+    let expected = vec![
+        113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 2, 3, 1, 85, 127, 192, 38, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    let encoded = encode(&image, QoiHeader::new(2, 2, 3, ColorSpace::Linear));
+    assert_eq!(encoded, Ok(expected));
+}
+
+#[test]
+fn test_decode_2x2_rgb_channels() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 2, 3, 1, 85, 127, 192, 38, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+
+    let black: [u8; 3] = [0, 0, 0];
+    let white: [u8; 3] = [255, 255, 255];
+    let expected = [[white, black], [black, white]].concat().concat();
+
+    let decoded = decode(&data);
+    assert_eq!(
+        decoded,
+        Ok((QoiHeader::new(2, 2, 3, ColorSpace::Linear), expected))
+    );
+}
+
+#[test]
+fn test_encode_rgb_never_emits_rgba() {
+    let image = Rng(0x2545_f491).bytes(16 * 16 * 3);
+
+    let encoded = encode(&image, QoiHeader::new(16, 16, 3, ColorSpace::Srgb)).unwrap();
+    let mut body = &encoded[14..encoded.len() - 8];
+    let mut rgb_ops = 0;
+    while let [op, ..] = body {
+        let op_len = match op {
+            0b11111111 => panic!("QOI_OP_RGBA in an RGB image"),
+            0b11111110 => {
+                rgb_ops += 1;
+                4
+            }
+            0b10000000..=0b10111111 => 2,
+            _ => 1,
+        };
+        body = &body[op_len..];
+    }
+    assert!(rgb_ops > 0);
+    assert_eq!(decode(&encoded).unwrap().1, image);
+}
+
+#[test]
+fn test_encode_decode_go_both_channel_counts() {
+    let img = ImageReader::open("../go.jpg").unwrap().decode().unwrap();
+    for (channels, data) in [(3, img.to_rgb8().to_vec()), (4, img.to_rgba8().to_vec())] {
+        let header = QoiHeader::new(img.width(), img.height(), channels, ColorSpace::Srgb);
+        let encoded = encode(&data, header).unwrap();
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.0, header);
+        assert!(
+            decoded.1 == data,
+            "data not the same for {channels} channels"
+        );
+    }
+}
+
+#[test]
+fn test_read_header_and_probe() {
+    let expected = QoiHeader::new(1, 1, 4, ColorSpace::Linear);
+
+    let mut encoded = Vec::new();
+    File::open("../go.qoi")
+        .unwrap()
+        .read_to_end(&mut encoded)
+        .unwrap();
+    let header = read_header(&encoded).unwrap();
+    assert_eq!(probe(File::open("../go.qoi").unwrap()), Ok(header));
+    assert_eq!(
+        header.decoded_size().unwrap(),
+        decode(&encoded).unwrap().1.len()
+    );
+
+    let only_header = [113, 111, 105, 102, 0, 0, 0, 1, 0, 0, 0, 1, 4, 1];
+    assert_eq!(read_header(&only_header), Ok(expected));
+    assert_eq!(probe(&only_header[..]), Ok(expected));
+    assert_eq!(expected.decoded_size(), Some(4));
+
+    // The header of a hostile file may describe more bytes than fit in memory.
+    let huge = read_header(&[
+        113, 111, 105, 102, 255, 255, 255, 255, 255, 255, 255, 255, 4, 0,
+    ]);
+    assert_eq!(huge.unwrap().decoded_size(), None);
+
+    assert_eq!(read_header(&only_header[..13]), Err(QoiError::TooShort));
+    assert_eq!(probe(&only_header[..13]), Err(QoiError::TooShort));
+    assert_eq!(
+        read_header(b"qoiX\0\0\0\x01\0\0\0\x01\x04\x01"),
+        Err(QoiError::BadMagic)
+    );
+}
+
+#[test]
+fn test_decode_limits() {
+    // A 22-byte file claiming to be 65535 x 65535 pixels.
+    let bomb = vec![
+        113, 111, 105, 102, 0, 0, 255, 255, 0, 0, 255, 255, 4, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    assert_eq!(decode(&bomb).err(), Some(QoiError::LimitExceeded));
+    assert_eq!(
+        QoiReader::new(bomb.as_slice()).err(),
+        Some(QoiError::LimitExceeded)
+    );
+    assert_eq!(
+        PushDecoder::new().feed(&bomb).err(),
+        Some(QoiError::LimitExceeded)
+    );
+
+    let header = [113, 111, 105, 102, 0, 0, 0, 8, 0, 0, 0, 4, 4, 0];
+    let limited = |options: DecodeOptions| {
+        Decoder::with_options(&[&header[..], &[0; 8]].concat(), options)
+            .err()
+            .unwrap()
+    };
+    let defaults = DecodeOptions::default();
+    let width = DecodeOptions {
+        max_width: 7,
+        ..defaults
+    };
+    let height = DecodeOptions {
+        max_height: 3,
+        ..defaults
+    };
+    let pixels = DecodeOptions {
+        max_pixels: 31,
+        ..defaults
+    };
+    let bytes = DecodeOptions {
+        max_output_bytes: 127,
+        ..defaults
+    };
+    for options in [width, height, pixels, bytes] {
+        assert_eq!(limited(options), QoiError::LimitExceeded);
+    }
+
+    let largest = [
+        113, 111, 105, 102, 255, 255, 255, 255, 255, 255, 255, 255, 4, 0,
+    ];
+    let unlimited = DecodeOptions::unlimited();
+    assert!(unlimited.check(&read_header(&largest).unwrap()).is_err());
+    assert_eq!(unlimited.check(&read_header(&header).unwrap()), Ok(128));
+}
+
+#[test]
+fn test_decode_strict_trailing_data() {
+    let data = vec![
+        113,
+        111,
+        105,
+        102,
+        0,
+        0,
+        0,
+        2,
+        0,
+        0,
+        0,
+        1,
+        4,
+        1,
+        QOI_OP_RUN | 1,
+        QOI_OP_RUN,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        1,
+    ];
+    assert_eq!(decode(&data), Err(QoiError::TrailingData));
+
+    let decoder = Decoder::with_options(&data, DecodeOptions::lenient()).unwrap();
+    let black: [u8; 4] = [0, 0, 0, 255];
+    assert_eq!(
+        decoder.decode_with_warnings(),
+        Ok((black.repeat(2), vec![QoiError::TrailingData]))
+    );
+}
+
+#[test]
+fn test_decode_lenient() {
+    let header = [113, 111, 105, 102, 0, 0, 0, 4, 0, 0, 0, 1, 3, 0];
+    let grey: [u8; 3] = [10, 10, 10];
+    let white: [u8; 3] = [255, 255, 255];
+    let lenient = |body: &[u8]| {
+        Decoder::with_options(&[&header[..], body].concat(), DecodeOptions::lenient())
+            .unwrap()
+            .decode_with_warnings()
+    };
+
+    // Too few pixels: the last pixel is repeated.
+    assert_eq!(
+        lenient(&[0b11111110, 10, 10, 10, 0, 0, 0, 0, 0, 0, 0, 1]),
+        Ok((grey.repeat(4), vec![QoiError::TooFewPixels]))
+    );
+    // Too many pixels: the run is cut short.
+    assert_eq!(
+        lenient(&[
+            0b11111110,
+            10,
+            10,
+            10,
+            QOI_OP_RUN | 4,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1
+        ]),
+        Ok((grey.repeat(4), vec![QoiError::TooManyPixels]))
+    );
+    // No end marker: the file was cut off in the middle of an opcode.
+    assert_eq!(
+        lenient(&[0b11111110, 10, 10, 10, 0b11111110, 255, 255]),
+        Ok((
+            [grey, grey, grey, grey].concat(),
+            vec![
+                QoiError::BadFooter,
+                QoiError::TruncatedOp,
+                QoiError::TooFewPixels
+            ]
+        ))
+    );
+    assert_eq!(
+        lenient(&[0b11111110, 255, 255, 255, QOI_OP_RUN | 2]),
+        Ok((white.repeat(4), vec![QoiError::BadFooter]))
+    );
+    assert_eq!(
+        Decoder::with_options(&header[..], DecodeOptions::lenient())
+            .unwrap()
+            .decode_with_warnings(),
+        Ok((
+            vec![0; 12],
+            vec![QoiError::BadFooter, QoiError::TooFewPixels]
+        ))
+    );
+}
+
+#[test]
+fn test_encode_decode_alpha_change_within_luma_range() {
+    // Found by the round_trip fuzz target: a small colour change combined with an alpha change
+    // was written as QOI_OP_LUMA, which cannot represent alpha.
+    let image = [[255, 255, 255, 3], [255, 255, 255, 255]].concat();
+
+    let encoded = encode(&image, QoiHeader::new(2, 1, 4, ColorSpace::Srgb));
+    let decoded = decode(&encoded.unwrap());
+    assert_eq!(decoded.unwrap().1, image);
+}
+
+#[test]
+fn test_encode_into_decode_into_reuse_buffers() {
+    let img = ImageReader::open("../go.jpg").unwrap().decode().unwrap();
+    let mut encoded = Vec::new();
+    let mut decoded = vec![0; img.width() as usize * img.height() as usize * 4];
+    for (channels, data) in [(3, img.to_rgb8().to_vec()), (4, img.to_rgba8().to_vec())] {
+        let header = QoiHeader::new(img.width(), img.height(), channels, ColorSpace::Srgb);
+        encoded.clear();
+        encode_into(&data, header, &mut encoded).unwrap();
+        assert_eq!(encoded, encode(&data, header).unwrap());
+
+        assert_eq!(decode_into(&encoded, &mut decoded), Ok(header));
+        assert!(decoded[..data.len()] == data);
+    }
+}
+
+#[test]
+fn test_encode_to_slice_worst_case() {
+    // Noise in every channel, so almost every pixel needs a full QOI_OP_RGBA.
+    let image = Rng(0x2545_f491).bytes(16 * 16 * 4);
+    let header = QoiHeader::new(16, 16, 4, ColorSpace::Srgb);
+    let expected = encode(&image, header).unwrap();
+
+    let mut out = vec![0; max_encoded_size(16, 16, 4)];
+    assert!(expected.len() <= out.len());
+    let len = encode_to_slice(&image, header, &mut out).unwrap();
+    assert_eq!(out[..len], expected);
+
+    let mut short = vec![0; expected.len() - 1];
+    assert_eq!(
+        encode_to_slice(&image, header, &mut short),
+        Err(QoiError::BufferTooSmall)
+    );
+}
+
+#[test]
+fn test_max_encoded_size() {
+    assert_eq!(max_encoded_size(0, 0, 4), 22);
+    assert_eq!(max_encoded_size(2, 3, 3), 22 + 6 * 4);
+    assert_eq!(max_encoded_size(u32::MAX, u32::MAX, 4), usize::MAX);
+}
+
+#[test]
+fn test_decode_into_buffer_too_small() {
+    let encoded = encode(
+        &[0, 0, 0, 255, 1, 1, 1, 255],
+        QoiHeader::new(2, 1, 4, ColorSpace::Srgb),
+    )
+    .unwrap();
+    let mut out = [0; 7];
+    assert_eq!(
+        decode_into(&encoded, &mut out),
+        Err(QoiError::BufferTooSmall)
+    );
+}
+
+#[test]
+fn test_decode_pixel_formats() {
+    let image = [[255, 128, 8, 255], [10, 20, 30, 40]].concat();
+    let encoded = encode(&image, QoiHeader::new(2, 1, 4, ColorSpace::Srgb)).unwrap();
+    let cases: [(PixelFormat, &[u8]); 7] = [
+        (PixelFormat::Rgb, &[255, 128, 8, 10, 20, 30]),
+        (PixelFormat::Rgba, &image),
+        (PixelFormat::Bgra, &[8, 128, 255, 255, 30, 20, 10, 40]),
+        (PixelFormat::Argb, &[255, 255, 128, 8, 40, 10, 20, 30]),
+        // 11111 100000 00001 and 00001 000101 00011
+        (PixelFormat::Rgb565, &[0x01, 0xfc, 0xa3, 0x08]),
+        (PixelFormat::Luma8, &[153, 18]),
+        (PixelFormat::LumaA8, &[153, 255, 18, 40]),
+    ];
+    for (format, expected) in cases {
+        let options = DecodeOptions {
+            format: Some(format),
+            ..DecodeOptions::default()
+        };
+        let decoder = Decoder::with_options(&encoded, options).unwrap();
+        assert_eq!(decoder.decoded_size(), expected.len());
+        assert_eq!(decoder.decode().unwrap(), expected, "{format:?}");
+
+        let mut reader = QoiReader::with_options(encoded.as_slice(), options).unwrap();
+        assert_eq!(reader.read_row().unwrap(), Some(expected), "{format:?}");
+
+        let mut push = PushDecoder::with_options(options);
+        push.feed(&encoded).unwrap();
+        assert_eq!(
+            push.drain_pixels().collect::<Vec<_>>(),
+            expected,
+            "{format:?}"
+        );
+    }
+}
+
+#[test]
+fn test_decode_go_as_bgra() {
+    let mut encoded = Vec::new();
+    File::open("../go.qoi")
+        .unwrap()
+        .read_to_end(&mut encoded)
+        .unwrap();
+    let (header, rgba) = decode(&encoded).unwrap();
+    assert_eq!(header.channels, 4);
+    let options = DecodeOptions {
+        format: Some(PixelFormat::Bgra),
+        ..DecodeOptions::default()
+    };
+    let bgra = Decoder::with_options(&encoded, options)
+        .unwrap()
+        .decode()
+        .unwrap();
+    for (rgba, bgra) in rgba.chunks_exact(4).zip(bgra.chunks_exact(4)) {
+        assert_eq!([rgba[2], rgba[1], rgba[0], rgba[3]], bgra);
+    }
+}
+
+#[test]
+fn test_encode_padded_bgrx_rows() {
+    let img = ImageReader::open("../go.jpg").unwrap().decode().unwrap();
+    let (width, height) = (img.width(), img.height());
+    let rgba = img.to_rgba8().to_vec();
+    // A framebuffer with 16 bytes of padding after every row and garbage in the X byte.
+    let stride = width as usize * 4 + 16;
+    let mut bgrx = vec![0xaa; stride * height as usize];
+    for (row, pixels) in bgrx.chunks_mut(stride).zip(rgba.chunks(width as usize * 4)) {
+        for (out, pixel) in row.chunks_exact_mut(4).zip(pixels.chunks_exact(4)) {
+            out.copy_from_slice(&[pixel[2], pixel[1], pixel[0], 7]);
+        }
+    }
+    let layout = SourceLayout {
+        format: PixelFormat::Bgra,
+        stride,
+        ignore_alpha: true,
+        premultiplied: false,
+    };
+
+    let header = QoiHeader::new(width, height, 4, ColorSpace::Srgb);
+    let encoded = Encoder::new(header).with_layout(layout).encode(&bgrx);
+    assert_eq!(encoded.unwrap(), encode(&rgba, header).unwrap());
+
+    // The last row does not need its padding, and RGB output drops alpha anyway.
+    let bgra = &bgrx[..bgrx.len() - 16];
+    let rgb = img.to_rgb8().to_vec();
+    let header = QoiHeader::new(width, height, 3, ColorSpace::Srgb);
+    let layout = SourceLayout {
+        ignore_alpha: false,
+        ..layout
+    };
+    let encoded = Encoder::new(header).with_layout(layout).encode(bgra);
+    assert_eq!(encoded.unwrap(), encode(&rgb, header).unwrap());
+}
+
+#[test]
+fn test_encode_layout_errors() {
+    let header = QoiHeader::new(2, 2, 4, ColorSpace::Srgb);
+    let layout = SourceLayout {
+        format: PixelFormat::Rgba,
+        stride: 7,
+        ignore_alpha: false,
+        premultiplied: false,
+    };
+    let encoded = Encoder::new(header).with_layout(layout).encode(&[0; 16]);
+    assert_eq!(encoded, Err(QoiError::InvalidStride));
+
+    let layout = SourceLayout {
+        stride: 12,
+        ..layout
+    };
+    for len in [19, 25] {
+        let encoded = Encoder::new(header)
+            .with_layout(layout)
+            .encode(&vec![0; len]);
+        assert_eq!(
+            encoded,
+            Err(QoiError::DataSizeMismatch {
+                expected: 20,
+                actual: len
+            })
+        );
+    }
+}
+
+#[test]
+fn test_encode_from_other_layouts() {
+    let header = QoiHeader::new(2, 1, 4, ColorSpace::Srgb);
+    let cases: [(PixelFormat, &[u8], [u8; 8]); 4] = [
+        (
+            PixelFormat::Argb,
+            &[40, 1, 2, 3, 255, 4, 5, 6],
+            [1, 2, 3, 40, 4, 5, 6, 255],
+        ),
+        (
+            PixelFormat::Rgb565,
+            &[0x01, 0xfc, 0xa3, 0x08],
+            [255, 130, 8, 255, 8, 20, 24, 255],
+        ),
+        (
+            PixelFormat::Luma8,
+            &[9, 200],
+            [9, 9, 9, 255, 200, 200, 200, 255],
+        ),
+        (
+            PixelFormat::LumaA8,
+            &[9, 1, 200, 2],
+            [9, 9, 9, 1, 200, 200, 200, 2],
+        ),
+    ];
+    for (format, data, expected) in cases {
+        let layout = SourceLayout::packed(format, header.width);
+        let encoded = Encoder::new(header).with_layout(layout).encode(data);
+        assert_eq!(decode(&encoded.unwrap()).unwrap().1, expected, "{format:?}");
+    }
+}
+
+#[test]
+fn test_premultiplied_round_trip() {
+    let premultiplied = [
+        [255, 128, 0, 255],
+        [0, 0, 0, 0],
+        [17, 34, 51, 255],
+        [64, 32, 0, 128],
+        [0, 0, 0, 0],
+        [0, 0, 0, 255],
+    ]
+    .concat();
+    let header = QoiHeader::new(3, 2, 4, ColorSpace::Srgb);
+    let layout = SourceLayout {
+        premultiplied: true,
+        ..SourceLayout::packed(PixelFormat::Rgba, header.width)
+    };
+    let encoded = Encoder::new(header)
+        .with_layout(layout)
+        .encode(&premultiplied);
+    let encoded = encoded.unwrap();
+
+    let straight = decode(&encoded).unwrap().1;
+    assert_eq!(straight[12..16], [128, 64, 0, 128]);
+
+    let options = DecodeOptions {
+        premultiplied: true,
+        ..DecodeOptions::default()
+    };
+    let decoded = Decoder::with_options(&encoded, options).unwrap().decode();
+    assert_eq!(decoded.unwrap(), premultiplied);
+
+    let mut reader = QoiReader::with_options(encoded.as_slice(), options).unwrap();
+    let mut decoded = vec![0; premultiplied.len()];
+    assert_eq!(reader.read_pixels(&mut decoded), Ok(6));
+    assert_eq!(decoded, premultiplied);
+
+    let mut push = PushDecoder::with_options(options);
+    push.feed(&encoded).unwrap();
+    assert_eq!(push.drain_pixels().collect::<Vec<_>>(), premultiplied);
+}
+
+#[test]
+fn test_decode_premultiplied() {
+    let straight = [[200, 100, 50, 128], [200, 100, 50, 0], [9, 8, 7, 255]].concat();
+    let encoded = encode(&straight, QoiHeader::new(3, 1, 4, ColorSpace::Srgb)).unwrap();
+    let options = DecodeOptions {
+        premultiplied: true,
+        ..DecodeOptions::default()
+    };
+    let decoded = Decoder::with_options(&encoded, options).unwrap().decode();
+    assert_eq!(
+        decoded.unwrap(),
+        [100, 50, 25, 128, 0, 0, 0, 0, 9, 8, 7, 255]
+    );
+}
@@ -0,0 +1,377 @@
+//! Synthetic images with the exact bytes the reference encoder from the QOI specification writes
+//! for them, worked out op by op in the comments. Every case is checked in both directions.
+//! Streams the encoder never writes are checked against a port of the reference decoder.
+//!
+//! Where this crate knowingly differs from the reference implementation:
+//! - Images with a width or height of 0 are encoded and decoded as an empty image, where the
+//!   reference rejects them.
+//! - The encoder takes images of any size, the reference refuses more than 400 million pixels.
+//!   The decoder applies the same limit through [`DecodeOptions::default`].
+//! - The decoder rejects a missing end marker, bytes after it and opcodes that describe too few
+//!   or too many pixels, where the reference quietly repeats or drops pixels.
+//!   [`DecodeOptions::lenient`] decodes such files the way the reference does, except that the
+//!   reference takes the last 8 bytes to be the end marker even when they are not.
+//! - Files may carry metadata chunks after the end marker, see [`rustqoi::set_metadata`]. The
+//!   reference never reads past the end marker, so it decodes them unchanged.
+
+use rustqoi::{decode, encode, set_metadata, Chunk, ColorSpace, DecodeOptions, Decoder, QoiHeader};
+
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Encodes `pixels`, expecting `header` followed by `ops` and the end marker, and decodes that
+/// file back to `pixels`.
+fn check(name: &str, header: QoiHeader, pixels: &[u8], ops: &[u8]) {
+    let mut expected = header_bytes(header);
+    expected.extend_from_slice(ops);
+    expected.extend_from_slice(&END_MARKER);
+
+    assert_eq!(encode(pixels, header).unwrap(), expected, "encoding {name}");
+    assert_eq!(
+        decode(&expected).unwrap(),
+        (header, pixels.to_vec()),
+        "decoding {name}"
+    );
+}
+
+/// A port of the decode loop of the reference decoder, which reads ops until every pixel is
+/// filled and ignores whatever follows.
+fn reference_decode(data: &[u8]) -> Vec<u8> {
+    let width = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(data[8..12].try_into().unwrap()) as usize;
+    let channels = data[12] as usize;
+    let chunks_len = data.len() - END_MARKER.len();
+    let mut index = [[0u8; 4]; 64];
+    let [mut r, mut g, mut b, mut a] = [0u8, 0, 0, 255];
+    let mut run = 0;
+    let mut p = 14;
+    let mut out = Vec::with_capacity(width * height * channels);
+    for _ in 0..width * height {
+        if run > 0 {
+            run -= 1;
+        } else if p < chunks_len {
+            let b1 = data[p];
+            p += 1;
+            if b1 == 0xfe {
+                [r, g, b] = data[p..p + 3].try_into().unwrap();
+                p += 3;
+            } else if b1 == 0xff {
+                [r, g, b, a] = data[p..p + 4].try_into().unwrap();
+                p += 4;
+            } else if b1 & 0xc0 == 0x00 {
+                [r, g, b, a] = index[b1 as usize];
+            } else if b1 & 0xc0 == 0x40 {
+                r = r.wrapping_add((b1 >> 4) & 3).wrapping_sub(2);
+                g = g.wrapping_add((b1 >> 2) & 3).wrapping_sub(2);
+                b = b.wrapping_add(b1 & 3).wrapping_sub(2);
+            } else if b1 & 0xc0 == 0x80 {
+                let b2 = data[p];
+                p += 1;
+                let vg = (b1 & 0x3f).wrapping_sub(32);
+                r = r.wrapping_add(vg.wrapping_sub(8).wrapping_add(b2 >> 4));
+                g = g.wrapping_add(vg);
+                b = b.wrapping_add(vg.wrapping_sub(8).wrapping_add(b2 & 0x0f));
+            } else {
+                run = b1 & 0x3f;
+            }
+            let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+            index[hash] = [r, g, b, a];
+        }
+        out.extend_from_slice(&[r, g, b, a][..channels]);
+    }
+    out
+}
+
+/// Decodes `ops`, a hand-written stream the encoder would never produce, with both the port of
+/// the reference decoder and this crate, expecting `pixels`.
+fn check_decode(name: &str, header: QoiHeader, ops: &[u8], pixels: &[u8]) {
+    let mut data = header_bytes(header);
+    data.extend_from_slice(ops);
+    data.extend_from_slice(&END_MARKER);
+    assert_eq!(reference_decode(&data), pixels, "reference decoding {name}");
+    assert_eq!(
+        decode(&data).unwrap(),
+        (header, pixels.to_vec()),
+        "decoding {name}"
+    );
+}
+
+fn header_bytes(header: QoiHeader) -> Vec<u8> {
+    let mut bytes = b"qoif".to_vec();
+    bytes.extend_from_slice(&header.width.to_be_bytes());
+    bytes.extend_from_slice(&header.height.to_be_bytes());
+    bytes.extend_from_slice(&[header.channels, header.colorspace.into()]);
+    bytes
+}
+
+fn rgba(pixels: &[[u8; 4]]) -> Vec<u8> {
+    pixels.concat()
+}
+
+fn rgb(pixels: &[[u8; 3]]) -> Vec<u8> {
+    pixels.concat()
+}
+
+#[test]
+fn test_conformance_initial_state() {
+    // The previous pixel starts as opaque black, so it is a run straight away.
+    check(
+        "opaque black",
+        QoiHeader::new(3, 1, 4, ColorSpace::Srgb),
+        &rgba(&[[0, 0, 0, 255]; 3]),
+        &[0xc2],
+    );
+    // The index starts as transparent black, which hashes to 0.
+    check(
+        "transparent black",
+        QoiHeader::new(2, 1, 4, ColorSpace::Srgb),
+        &rgba(&[[0, 0, 0, 0]; 2]),
+        &[0x00, 0xc0],
+    );
+}
+
+#[test]
+fn test_conformance_gradient() {
+    check(
+        "gray gradient",
+        QoiHeader::new(3, 2, 3, ColorSpace::Srgb),
+        &rgb(&[
+            [16, 16, 16],
+            [20, 20, 20],
+            [24, 24, 24],
+            [28, 28, 28],
+            [29, 30, 31],
+            [30, 31, 31],
+        ]),
+        &[
+            0xb0, 0x88, // luma, dg 16
+            0xa4, 0x88, // luma, dg 4
+            0xa4, 0x88, // luma, dg 4
+            0xa4, 0x88, // luma, dg 4
+            0xa2, 0x79, // luma, dg 2, dr - dg -1, db - dg 1
+            0x7e, // diff 1, 1, 0
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_noise() {
+    check(
+        "noise",
+        QoiHeader::new(2, 2, 3, ColorSpace::Linear),
+        &rgb(&[[200, 13, 77], [5, 250, 140], [77, 77, 77], [200, 13, 77]]),
+        &[
+            0xfe, 200, 13, 77, // rgb, hash 41
+            0xfe, 5, 250, 140, // rgb, hash 58
+            0xfe, 77, 77, 77,   // rgb, hash 56
+            0x29, // index 41
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_alpha_ramp() {
+    // Alpha contributes 11 * a to the hash, so the first three all land in slot 28.
+    check(
+        "alpha ramp",
+        QoiHeader::new(5, 1, 4, ColorSpace::Srgb),
+        &rgba(&[
+            [100, 100, 100, 0],
+            [100, 100, 100, 64],
+            [100, 100, 100, 128],
+            [100, 100, 100, 255],
+            [100, 100, 100, 128],
+        ]),
+        &[
+            0xff, 100, 100, 100, 0, // rgba, hash 28
+            0xff, 100, 100, 100, 64, // rgba, hash 28
+            0xff, 100, 100, 100, 128, // rgba, hash 28
+            0xff, 100, 100, 100, 255,  // rgba, a change rules out diff and luma
+            0x1c, // index 28
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_runs_of_62() {
+    let pixel = [10, 20, 30, 255];
+    for (repeats, runs) in [
+        (61, &[0xfc][..]),
+        (62, &[0xfd][..]),
+        (63, &[0xfd, 0xc0][..]),
+        (124, &[0xfd, 0xfd][..]),
+        (125, &[0xfd, 0xfd, 0xc0][..]),
+    ] {
+        let mut ops = vec![0xfe, 10, 20, 30];
+        ops.extend_from_slice(runs);
+        check(
+            &format!("{repeats} repeats"),
+            QoiHeader::new(1 + repeats, 1, 4, ColorSpace::Srgb),
+            &rgba(&vec![pixel; 1 + repeats as usize]),
+            &ops,
+        );
+    }
+    // A run carries on across rows and is written once the image ends.
+    check(
+        "run across rows",
+        QoiHeader::new(2, 2, 3, ColorSpace::Srgb),
+        &rgb(&[[10, 20, 30]; 4]),
+        &[0xfe, 10, 20, 30, 0xc2],
+    );
+}
+
+#[test]
+fn test_conformance_index_collisions() {
+    // (10, 20, 30) and (10, 20, 94) both hash to 9, each evicts the other.
+    check(
+        "index collisions",
+        QoiHeader::new(5, 1, 3, ColorSpace::Srgb),
+        &rgb(&[
+            [10, 20, 30],
+            [11, 20, 30],
+            [10, 20, 30],
+            [10, 20, 94],
+            [10, 20, 30],
+        ]),
+        &[
+            0xfe, 10, 20, 30,   // rgb, hash 9
+            0x7a, // diff 1, 0, 0, hash 12
+            0x09, // index 9
+            0xfe, 10, 20, 94, // rgb, db 64 is too far for luma, evicts slot 9
+            0xfe, 10, 20, 30, // rgb, slot 9 no longer holds it
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_wrapping_diffs() {
+    check(
+        "wrapping diffs",
+        QoiHeader::new(4, 1, 3, ColorSpace::Srgb),
+        &rgb(&[[255, 255, 255], [1, 1, 1], [254, 0, 2], [8, 10, 12]]),
+        &[
+            0x55, // diff -1, -1, -1
+            0xa2, 0x88, // luma, dg 2
+            0x9f, 0x6a, // luma, dg -1, dr - dg -2, db - dg 2
+            0xaa, 0x88, // luma, dg 10
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_op_ranges() {
+    check(
+        "op ranges",
+        QoiHeader::new(6, 1, 3, ColorSpace::Srgb),
+        &rgb(&[
+            [100, 100, 100],
+            [60, 68, 75],
+            [98, 99, 98],
+            [130, 131, 130],
+            [128, 132, 129],
+            [130, 131, 130],
+        ]),
+        &[
+            0xfe, 100, 100, 100, // rgb, dg 100
+            0x80, 0x0f, // luma, dg -32, dr - dg -8, db - dg 7
+            0xbf, 0xf0, // luma, dg 31, dr - dg 7, db - dg -8
+            0xfe, 130, 131, 130,  // rgb, dg 32, hash 24
+            0x4d, // diff -2, 1, -1
+            0x18, // index 24
+        ],
+    );
+}
+
+#[test]
+fn test_conformance_index_after_every_op() {
+    // Every op stores the pixel it decodes in the index, so the next op may refer to it.
+    let p1 = [1, 0, 255, 255];
+    let p2 = [10, 20, 30, 40];
+    let p3 = [12, 22, 32, 40];
+    let p4 = [7, 7, 7, 40];
+    check_decode(
+        "index after every op",
+        QoiHeader::new(8, 1, 4, ColorSpace::Srgb),
+        &[
+            0x79, // diff 1, 0, -1, hash 49
+            0x31, // index 49
+            0xff, 10, 20, 30, 40,   // rgba, hash 12
+            0x0c, // index 12
+            0xa2, 0x88, // luma, dg 2, hash 42
+            0x2a, // index 42
+            0xfe, 7, 7, 7,    // rgb, hash 33
+            0x21, // index 33
+        ],
+        &rgba(&[p1, p1, p2, p2, p3, p3, p4, p4]),
+    );
+    // A run stores its pixel too, which only shows for the opaque black before any other op.
+    check_decode(
+        "index after run",
+        QoiHeader::new(2, 1, 4, ColorSpace::Srgb),
+        &[
+            0xc0, // run 1
+            0x35, // index 53, opaque black
+        ],
+        &rgba(&[[0, 0, 0, 255]; 2]),
+    );
+    check_decode(
+        "index after run, rgb",
+        QoiHeader::new(3, 1, 3, ColorSpace::Srgb),
+        &[
+            0xfe, 10, 20, 30,   // rgb, hash 9
+            0xc0, // run 1
+            0x09, // index 9
+        ],
+        &rgb(&[[10, 20, 30]; 3]),
+    );
+}
+
+#[test]
+fn test_conformance_lenient_matches_reference_decoder() {
+    // Missing pixels repeat the last one, surplus pixels and trailing bytes are dropped.
+    let header = QoiHeader::new(3, 1, 3, ColorSpace::Srgb);
+    for ops in [
+        &[0xfe, 10, 20, 30][..],
+        &[0xfe, 10, 20, 30, 0xc3],
+        &[0xfe, 10, 20, 30, 0xc1, 0x7e, 0x7e, 0x7e],
+    ] {
+        let mut data = header_bytes(header);
+        data.extend_from_slice(ops);
+        data.extend_from_slice(&END_MARKER);
+        let decoder = Decoder::with_options(&data, DecodeOptions::lenient()).unwrap();
+        assert_eq!(decoder.decode().unwrap(), reference_decode(&data));
+        assert!(decode(&data).is_err());
+    }
+
+    // Without an end marker the reference still takes the last 8 bytes to be one, where the
+    // lenient decoder decodes them.
+    let mut data = header_bytes(header);
+    data.extend_from_slice(&[0xfe, 10, 20, 30]);
+    let decoder = Decoder::with_options(&data, DecodeOptions::lenient()).unwrap();
+    assert_eq!(decoder.decode().unwrap(), [10, 20, 30].repeat(3));
+    assert_eq!(reference_decode(&data), [0, 0, 0].repeat(3));
+}
+
+#[test]
+fn test_conformance_reference_decoder_ignores_metadata() {
+    let header = QoiHeader::new(5, 3, 4, ColorSpace::Srgb);
+    let pixels: Vec<u8> = (0..header.n_pixels().unwrap() as u8)
+        .flat_map(|i| [i * 16, 255 - i, i / 4 * 20, 200 + i % 2])
+        .collect();
+    let plain = encode(&pixels, header).unwrap();
+    assert_eq!(reference_decode(&plain), pixels);
+
+    // A trailer that itself looks like ops and an end marker.
+    let tricky = [0xfe, 1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 1];
+    let data = set_metadata(
+        &plain,
+        &[
+            Chunk::new(Chunk::ICC_PROFILE, &tricky),
+            Chunk::new(*b"zzzz", &[0xff; 40]),
+        ],
+    )
+    .unwrap();
+    assert_eq!(reference_decode(&data), pixels);
+    assert_eq!(decode(&data).unwrap(), (header, pixels.clone()));
+    let decoder = Decoder::with_options(&data, DecodeOptions::lenient()).unwrap();
+    assert_eq!(decoder.decode().unwrap(), pixels);
+}
@@ -0,0 +1,198 @@
+//! Compares the encoder against a plain pixel-by-pixel port of the reference encoder, on images
+//! built to hit the run scanning at every length and position.
+
+mod common;
+
+use common::Rng;
+use rustqoi::{decode, encode, ColorSpace, Encoder, PixelFormat, QoiHeader, SourceLayout};
+
+/// The encoder from the QOI specification, one pixel at a time with no scanning ahead.
+fn reference_encode(data: &[u8], header: QoiHeader) -> Vec<u8> {
+    let channels = header.channels as usize;
+    let mut out = b"qoif".to_vec();
+    out.extend_from_slice(&header.width.to_be_bytes());
+    out.extend_from_slice(&header.height.to_be_bytes());
+    out.extend_from_slice(&[header.channels, header.colorspace.into()]);
+    let mut index = [[0u8; 4]; 64];
+    let mut previous = [0, 0, 0, 255];
+    let mut run = 0;
+    let pixels = data.chunks_exact(channels);
+    let last = pixels.len().saturating_sub(1);
+    for (i, raw) in pixels.enumerate() {
+        let pixel = [
+            raw[0],
+            raw[1],
+            raw[2],
+            if channels == 4 { raw[3] } else { 255 },
+        ];
+        if pixel == previous {
+            run += 1;
+            if run == 62 || i == last {
+                out.push(0xc0 | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+        if run > 0 {
+            out.push(0xc0 | (run - 1));
+            run = 0;
+        }
+        let [r, g, b, a] = pixel;
+        let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+        if index[hash] == pixel {
+            out.push(hash as u8);
+        } else {
+            index[hash] = pixel;
+            if a == previous[3] {
+                let dr = r.wrapping_sub(previous[0]) as i8;
+                let dg = g.wrapping_sub(previous[1]) as i8;
+                let db = b.wrapping_sub(previous[2]) as i8;
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+                if (-2..2).contains(&dr) && (-2..2).contains(&dg) && (-2..2).contains(&db) {
+                    out.push(0x40 | ((dr + 2) << 4 | (dg + 2) << 2 | (db + 2)) as u8);
+                } else if (-32..32).contains(&dg)
+                    && (-8..8).contains(&dr_dg)
+                    && (-8..8).contains(&db_dg)
+                {
+                    out.push(0x80 | (dg + 32) as u8);
+                    out.push(((dr_dg + 8) << 4 | (db_dg + 8)) as u8);
+                } else {
+                    out.extend_from_slice(&[0xfe, r, g, b]);
+                }
+            } else {
+                out.extend_from_slice(&[0xff, r, g, b, a]);
+            }
+        }
+        previous = pixel;
+    }
+    out.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    out
+}
+
+/// Runs of random length of a few random colors, with the odd single-byte change so runs are
+/// broken at every position within a pixel.
+fn runs_image(rng: &mut Rng, channels: usize, pixels: usize) -> Vec<u8> {
+    let palette: Vec<[u8; 4]> = (0..4)
+        .map(|_| rng.next().to_le_bytes()[..4].try_into().unwrap())
+        .collect();
+    let mut data = Vec::with_capacity(pixels * channels);
+    while data.len() < pixels * channels {
+        let mut pixel = palette[rng.below(4) as usize];
+        if rng.below(4) == 0 {
+            pixel[rng.below(4) as usize] ^= 1;
+        }
+        let len = match rng.below(3) {
+            0 => rng.below(4),
+            1 => rng.below(40),
+            _ => rng.below(200),
+        };
+        for _ in 0..len {
+            data.extend_from_slice(&pixel[..channels]);
+        }
+    }
+    data.truncate(pixels * channels);
+    data
+}
+
+#[test]
+fn test_encode_matches_reference_on_runs() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for channels in [3, 4] {
+        for (width, height) in [
+            (1, 1),
+            (1, 300),
+            (7, 13),
+            (61, 3),
+            (62, 5),
+            (63, 4),
+            (256, 64),
+        ] {
+            let data = runs_image(&mut rng, channels, width * height);
+            let header = QoiHeader::new(
+                width as u32,
+                height as u32,
+                channels as u8,
+                ColorSpace::Srgb,
+            );
+            let encoded = encode(&data, header).unwrap();
+            assert!(
+                encoded == reference_encode(&data, header),
+                "{width}x{height}, {channels} channels"
+            );
+            assert!(decode(&encoded).unwrap().1 == data);
+        }
+    }
+}
+
+#[test]
+fn test_encode_matches_reference_on_uniform_images() {
+    for channels in [3, 4] {
+        for len in [0, 1, 2, 3, 4, 5, 7, 8, 9, 61, 62, 63, 123, 124, 125, 1000] {
+            let data = [12, 34, 56, 78][..channels].repeat(len);
+            let header = QoiHeader::new(len as u32, 1, channels as u8, ColorSpace::Linear);
+            assert!(encode(&data, header).unwrap() == reference_encode(&data, header));
+        }
+    }
+}
+
+#[test]
+fn test_encode_matches_reference_on_noise() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for channels in [3, 4] {
+        let data: Vec<u8> = (0..64 * 64 * channels)
+            .map(|_| rng.below(4) as u8 * 64)
+            .collect();
+        let header = QoiHeader::new(64, 64, channels as u8, ColorSpace::Srgb);
+        assert!(encode(&data, header).unwrap() == reference_encode(&data, header));
+    }
+}
+
+#[test]
+fn test_encode_matches_reference_from_other_layouts() {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    let (width, height) = (50, 20);
+    let rgba = runs_image(&mut rng, 4, width * height);
+    let header = QoiHeader::new(width as u32, height as u32, 4, ColorSpace::Srgb);
+    let expected = reference_encode(&rgba, header);
+
+    // Padded BGRA rows, with padding that repeats the last pixel so a scan running past the end
+    // of a row would be caught.
+    let stride = width * 4 + 12;
+    let mut bgra = Vec::new();
+    for row in rgba.chunks(width * 4) {
+        for pixel in row.chunks(4) {
+            bgra.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+        let last = bgra[bgra.len() - 4..].to_vec();
+        bgra.extend_from_slice(&last.repeat(3));
+    }
+    let layout = SourceLayout {
+        stride,
+        ..SourceLayout::packed(PixelFormat::Bgra, width as u32)
+    };
+    let encoded = Encoder::new(header)
+        .with_layout(layout)
+        .encode(&bgra)
+        .unwrap();
+    assert!(encoded == expected);
+
+    // Alpha that is ignored may differ between pixels of a run.
+    let mut noisy_alpha = rgba.clone();
+    for pixel in noisy_alpha.chunks_mut(4) {
+        pixel[3] = rng.next() as u8;
+    }
+    let opaque: Vec<u8> = rgba
+        .chunks(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+        .collect();
+    let layout = SourceLayout {
+        ignore_alpha: true,
+        ..SourceLayout::packed(PixelFormat::Rgba, width as u32)
+    };
+    let encoded = Encoder::new(header)
+        .with_layout(layout)
+        .encode(&noisy_alpha)
+        .unwrap();
+    assert!(encoded == reference_encode(&opaque, header));
+}
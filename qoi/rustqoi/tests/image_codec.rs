@@ -0,0 +1,60 @@
+use image::{io::Reader as ImageReader, ColorType, DynamicImage, ImageEncoder, ImageError};
+use rustqoi::{decode, encode, ColorSpace, QoiHeader, QoiImageDecoder, QoiImageEncoder};
+use std::{fs::File, io::Read};
+
+#[test]
+fn test_dynamic_image_from_qoi() {
+    let mut encoded = Vec::new();
+    File::open("../go.qoi")
+        .unwrap()
+        .read_to_end(&mut encoded)
+        .unwrap();
+    let (header, pixels) = decode(&encoded).unwrap();
+
+    let decoder = QoiImageDecoder::new(File::open("../go.qoi").unwrap()).unwrap();
+    let img = DynamicImage::from_decoder(decoder).unwrap();
+    assert_eq!((img.width(), img.height()), (header.width, header.height));
+    assert!(img.as_bytes() == pixels, "data not the same");
+}
+
+#[test]
+fn test_dynamic_image_to_qoi() {
+    let img = ImageReader::open("../go.jpg").unwrap().decode().unwrap();
+    let expected = encode(
+        img.as_bytes(),
+        QoiHeader::new(img.width(), img.height(), 3, ColorSpace::Linear),
+    )
+    .unwrap();
+
+    let mut encoded = Vec::new();
+    img.write_with_encoder(QoiImageEncoder::new(&mut encoded).with_colorspace(ColorSpace::Linear))
+        .unwrap();
+    assert!(encoded == expected, "data not the same");
+
+    let decoder = QoiImageDecoder::new(encoded.as_slice()).unwrap();
+    let round_trip = DynamicImage::from_decoder(decoder).unwrap();
+    assert_eq!(round_trip, img);
+}
+
+#[test]
+fn test_unsupported_color_type() {
+    let img = DynamicImage::new_luma8(2, 2);
+    let result = img.write_with_encoder(QoiImageEncoder::new(Vec::new()));
+    assert!(matches!(result, Err(ImageError::Unsupported(_))));
+}
+
+#[test]
+fn test_encoding_huge_image() {
+    let result =
+        QoiImageEncoder::new(Vec::new()).write_image(&[0; 4], u32::MAX, u32::MAX, ColorType::Rgba8);
+    assert!(matches!(result, Err(ImageError::Encoding(_))));
+    let result =
+        QoiImageEncoder::new(Vec::new()).write_image(&[0; 4], 1 << 20, 1 << 20, ColorType::Rgba8);
+    assert!(matches!(result, Err(ImageError::Encoding(_))));
+}
+
+#[test]
+fn test_decoding_error() {
+    let result = QoiImageDecoder::new(&b"this is not a qoi file at all"[..]);
+    assert!(matches!(result, Err(ImageError::Decoding(_))));
+}
@@ -0,0 +1,78 @@
+use rustqoi::{
+    decode, decode_linear_f32, decode_linear_u16, encode, encode_linear_f32, encode_linear_u16,
+    ColorSpace, QoiError, QoiHeader,
+};
+
+/// Every byte value once in each channel, with alpha running the other way.
+fn all_bytes() -> Vec<u8> {
+    (0..=255u8).flat_map(|v| [v, v, 255 - v, 255 - v]).collect()
+}
+
+#[test]
+fn test_decode_linear_srgb() {
+    let image = [[0, 128, 255, 128], [188, 188, 188, 0]].concat();
+    let encoded = encode(&image, QoiHeader::new(2, 1, 4, ColorSpace::Srgb)).unwrap();
+    let (header, linear) = decode_linear_f32(&encoded).unwrap();
+    assert_eq!(header.colorspace, ColorSpace::Srgb);
+    let expected = [
+        0.0,
+        0.21586,
+        1.0,
+        128.0 / 255.0,
+        0.50289,
+        0.50289,
+        0.50289,
+        0.0,
+    ];
+    for (value, expected) in linear.iter().zip(expected) {
+        assert!((value - expected).abs() < 1e-5, "{value} != {expected}");
+    }
+
+    let (_, linear) = decode_linear_u16(&encoded).unwrap();
+    assert_eq!(linear, [0, 14146, 65535, 32896, 32957, 32957, 32957, 0]);
+}
+
+#[test]
+fn test_decode_linear_file_is_only_scaled() {
+    let image = [0, 128, 255];
+    let encoded = encode(&image, QoiHeader::new(1, 1, 3, ColorSpace::Linear)).unwrap();
+    let (_, linear) = decode_linear_u16(&encoded).unwrap();
+    assert_eq!(linear, [0, 32896, 65535]);
+}
+
+#[test]
+fn test_linear_round_trip() {
+    let image = all_bytes();
+    for colorspace in [ColorSpace::Srgb, ColorSpace::Linear] {
+        let header = QoiHeader::new(16, 16, 4, colorspace);
+        let encoded = encode(&image, header).unwrap();
+
+        let (_, linear) = decode_linear_f32(&encoded).unwrap();
+        let reencoded = encode_linear_f32(&linear, header).unwrap();
+        assert_eq!(decode(&reencoded).unwrap().1, image, "{colorspace:?}");
+
+        let (_, linear) = decode_linear_u16(&encoded).unwrap();
+        let reencoded = encode_linear_u16(&linear, header).unwrap();
+        assert_eq!(decode(&reencoded).unwrap().1, image, "{colorspace:?}");
+    }
+}
+
+#[test]
+fn test_encode_linear_clamps() {
+    let header = QoiHeader::new(2, 1, 3, ColorSpace::Srgb);
+    let encoded = encode_linear_f32(&[-1.0, 0.5, 2.0, f32::NAN, 0.0031308, 1.0], header).unwrap();
+    assert_eq!(decode(&encoded).unwrap().1, [0, 188, 255, 0, 10, 255]);
+
+    assert_eq!(
+        encode_linear_u16(&[0; 5], header),
+        Err(QoiError::DataSizeMismatch {
+            expected: 6,
+            actual: 5
+        })
+    );
+    let huge = QoiHeader::new(u32::MAX, u32::MAX, 4, ColorSpace::Srgb);
+    assert_eq!(
+        encode_linear_f32(&[0.0; 4], huge),
+        Err(QoiError::LimitExceeded)
+    );
+}
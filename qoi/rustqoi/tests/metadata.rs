@@ -0,0 +1,100 @@
+use rustqoi::{
+    decode, encode, read_metadata, set_metadata, strip_metadata, Chunk, ColorSpace, Decoder,
+    PushDecoder, QoiError, QoiHeader, QoiReader,
+};
+
+fn image() -> (QoiHeader, Vec<u8>, Vec<u8>) {
+    let header = QoiHeader::new(16, 9, 3, ColorSpace::Srgb);
+    let pixels: Vec<u8> = (0..header.decoded_size().unwrap())
+        .map(|i| (i * 7 % 253) as u8)
+        .collect();
+    let encoded = encode(&pixels, header).unwrap();
+    (header, pixels, encoded)
+}
+
+#[test]
+fn test_metadata_round_trip() {
+    let (header, pixels, encoded) = image();
+    let icc = [0x42; 300];
+    let exif = b"MM\x00\x2a\x00\x00\x00\x08";
+    let text = Chunk::text_data("Author", "Gopher");
+    let chunks = [
+        Chunk::new(Chunk::ICC_PROFILE, &icc),
+        Chunk::new(Chunk::EXIF, exif),
+        Chunk::new(Chunk::TEXT, &text),
+        Chunk::new(*b"empt", &[]),
+    ];
+    let data = set_metadata(&encoded, &chunks).unwrap();
+
+    assert_eq!(read_metadata(&data).unwrap().collect::<Vec<_>>(), chunks);
+    assert_eq!(chunks[2].text(), Some(("Author", "Gopher")));
+    assert_eq!(chunks[1].text(), None);
+    assert_eq!(strip_metadata(&data), encoded);
+
+    assert_eq!(decode(&data).unwrap(), (header, pixels.clone()));
+    let decoder = Decoder::new(&data).unwrap();
+    assert_eq!(decoder.decode().unwrap(), pixels);
+}
+
+#[test]
+fn test_metadata_replace_and_remove() {
+    let (_, _, encoded) = image();
+    assert_eq!(read_metadata(&encoded).unwrap().count(), 0);
+    assert_eq!(strip_metadata(&encoded), encoded);
+
+    let first = set_metadata(&encoded, &[Chunk::new(Chunk::EXIF, b"old")]).unwrap();
+    let second = set_metadata(&first, &[Chunk::new(Chunk::TEXT, b"a\0new")]).unwrap();
+    let chunks: Vec<_> = read_metadata(&second).unwrap().collect();
+    assert_eq!(chunks, [Chunk::new(Chunk::TEXT, b"a\0new")]);
+    assert_eq!(set_metadata(&second, &[]).unwrap(), encoded);
+
+    assert_eq!(
+        set_metadata(&encoded[..encoded.len() - 1], &[]),
+        Err(QoiError::BadFooter)
+    );
+}
+
+#[test]
+fn test_metadata_malformed() {
+    let (_, _, encoded) = image();
+    let data = set_metadata(&encoded, &[Chunk::new(Chunk::EXIF, b"exif data")]).unwrap();
+    let trailer = data.len() - 8;
+
+    // The total length reaches past the end marker.
+    let mut too_long = data.clone();
+    too_long[trailer + 3] += 1;
+    assert_eq!(read_metadata(&too_long).err(), Some(QoiError::BadMetadata));
+    assert_eq!(decode(&too_long).err(), Some(QoiError::BadFooter));
+
+    // The chunk length reaches past the trailer.
+    let mut bad_chunk = data.clone();
+    bad_chunk[encoded.len() + 7] += 1;
+    assert_eq!(read_metadata(&bad_chunk).err(), Some(QoiError::BadMetadata));
+    // The file itself is intact, only the metadata is unusable.
+    assert!(decode(&bad_chunk).is_ok());
+
+    assert_eq!(
+        read_metadata(&data[trailer + 2..]).err(),
+        Some(QoiError::BadMetadata)
+    );
+}
+
+#[test]
+fn test_metadata_ignored_by_streaming_decoders() {
+    let (header, pixels, encoded) = image();
+    let data = set_metadata(&encoded, &[Chunk::new(Chunk::ICC_PROFILE, &[1; 64])]).unwrap();
+
+    let mut decoder = PushDecoder::new();
+    let progress = decoder.feed(&data).unwrap();
+    assert!(progress.done);
+    assert_eq!(progress.consumed, encoded.len());
+    assert_eq!(decoder.drain_pixels().collect::<Vec<_>>(), pixels);
+
+    let mut reader = QoiReader::new(data.as_slice()).unwrap();
+    assert_eq!(reader.header(), &header);
+    let mut decoded = Vec::new();
+    while let Some(row) = reader.read_row().unwrap() {
+        decoded.extend_from_slice(row);
+    }
+    assert_eq!(decoded, pixels);
+}
@@ -0,0 +1,88 @@
+use rustqoi::{decode, Progress, PushDecoder, QoiError};
+use std::{fs::File, io::Read};
+
+fn go_qoi() -> Vec<u8> {
+    let mut buf = Vec::with_capacity(1_000_000);
+    File::open("../go.qoi")
+        .unwrap()
+        .read_to_end(&mut buf)
+        .unwrap();
+    buf
+}
+
+#[test]
+fn test_push_chunks_go() {
+    let encoded = go_qoi();
+    let (header, expected) = decode(&encoded).unwrap();
+
+    for chunk_size in [1, 2, 3, 5, 7, 4096] {
+        let mut decoder = PushDecoder::new();
+        let mut decoded = Vec::new();
+        let mut n_pixels = 0;
+        for chunk in encoded.chunks(chunk_size) {
+            let progress = decoder.feed(chunk).unwrap();
+            assert_eq!(progress.consumed, chunk.len());
+            n_pixels += progress.pixels;
+            decoded.extend(decoder.drain_pixels());
+        }
+        assert!(decoder.is_done());
+        assert_eq!(decoder.header(), Some(&header));
+        assert_eq!(n_pixels, header.n_pixels().unwrap());
+        assert!(
+            decoded == expected,
+            "data not the same for chunks of {chunk_size}"
+        );
+    }
+}
+
+#[test]
+fn test_push_split_rgba() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 1, 0, 0, 0, 1, 4, 0, 255, 10, 20, 30, 40, 0, 0, 0, 0, 0, 0, 0,
+        1, 42,
+    ];
+    let mut decoder = PushDecoder::new();
+    assert_eq!(
+        decoder.feed(&data[..17]),
+        Ok(Progress {
+            consumed: 17,
+            pixels: 0,
+            done: false
+        })
+    );
+    assert_eq!(decoder.drain_pixels().count(), 0);
+    assert_eq!(
+        decoder.feed(&data[17..]),
+        Ok(Progress {
+            consumed: 10,
+            pixels: 1,
+            done: true
+        })
+    );
+    assert_eq!(decoder.drain_pixels().collect::<Vec<_>>(), [10, 20, 30, 40]);
+}
+
+#[test]
+fn test_push_errors() {
+    let header = [113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 1, 4, 1];
+
+    let mut decoder = PushDecoder::new();
+    assert_eq!(
+        decoder
+            .feed(&[113, 111, 105, 103])
+            .and(decoder.feed(&header[4..])),
+        Err(QoiError::BadMagic)
+    );
+
+    let mut decoder = PushDecoder::new();
+    assert_eq!(
+        decoder.feed(&[&header[..], &[0b11000010]].concat()),
+        Err(QoiError::TooManyPixels)
+    );
+
+    let mut decoder = PushDecoder::new();
+    assert_eq!(
+        decoder.feed(&[&header[..], &[0b11000001, 0, 0, 0, 0, 0, 0, 0, 0]].concat()),
+        Err(QoiError::BadFooter)
+    );
+}
@@ -0,0 +1,72 @@
+use rustqoi::{decode, decode_strips, is_strips, ColorSpace};
+use std::{env, fs, path::PathBuf, process::Command};
+
+fn qoiconv(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_qoiconv"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("qoiconv-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn test_qoiconv_round_trip() {
+    let qoi = temp_path("go.qoi");
+    let png = temp_path("go.png");
+    let back = temp_path("back.qoi");
+
+    let output = qoiconv(&["--channels", "3", "../go.jpg", qoi.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let (header, pixels) = decode(&fs::read(&qoi).unwrap()).unwrap();
+    assert_eq!((header.channels, header.colorspace), (3, ColorSpace::Srgb));
+
+    let output = qoiconv(&[qoi.to_str().unwrap(), png.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+
+    let output = qoiconv(&["--linear", png.to_str().unwrap(), back.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let (back_header, back_pixels) = decode(&fs::read(&back).unwrap()).unwrap();
+    assert_eq!(back_header.colorspace, ColorSpace::Linear);
+    assert!(back_pixels == pixels, "png round trip is lossy");
+
+    for path in [qoi, png, back] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_qoiconv_strips() {
+    let strips = temp_path("strips.qoi");
+    let plain = temp_path("plain.qoi");
+
+    let output = qoiconv(&["--strips", "../go.jpg", strips.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let encoded = fs::read(&strips).unwrap();
+    assert!(is_strips(&encoded));
+
+    let output = qoiconv(&[strips.to_str().unwrap(), plain.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let plain_encoded = fs::read(&plain).unwrap();
+    assert!(!is_strips(&plain_encoded));
+    assert!(decode(&plain_encoded).unwrap() == decode_strips(&encoded).unwrap());
+
+    for path in [strips, plain] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_qoiconv_bad_input() {
+    let bad = temp_path("bad.qoi");
+    fs::write(&bad, b"this is not a qoi file at all").unwrap();
+    let output = qoiconv(&[bad.to_str().unwrap(), "out.png"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("magic is missing"));
+    fs::remove_file(bad).unwrap();
+
+    let output = qoiconv(&["--channels", "5", "in.png", "out.qoi"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,91 @@
+use image::{Rgb, RgbImage};
+use rustqoi::{ColorSpace, SequenceReader};
+use std::{env, fs, path::PathBuf, process::Command};
+
+fn qoiseq(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_qoiseq"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("qoiseq-{}-{name}", std::process::id()))
+}
+
+fn frame(i: u8) -> RgbImage {
+    RgbImage::from_fn(8, 4, |x, _| Rgb([i * 10, x as u8, 0]))
+}
+
+#[test]
+fn test_qoiseq_numbered_pngs() {
+    let dir = temp_path("frames");
+    let out = temp_path("anim.qoi");
+    fs::create_dir_all(&dir).unwrap();
+    // Numeric order differs from the order of the names.
+    for i in [1, 2, 10] {
+        frame(i).save(dir.join(format!("frame_{i}.png"))).unwrap();
+    }
+    fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+
+    let output = qoiseq(&[
+        "--duration",
+        "50",
+        "--loops",
+        "2",
+        "--keyframes",
+        "0",
+        "--linear",
+        dir.to_str().unwrap(),
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    let data = fs::read(&out).unwrap();
+    let reader = SequenceReader::new(data.as_slice()).unwrap();
+    let header = *reader.header();
+    assert_eq!(
+        (
+            header.width,
+            header.height,
+            header.channels,
+            header.colorspace
+        ),
+        (8, 4, 3, ColorSpace::Linear)
+    );
+    assert_eq!(reader.loops(), 2);
+    let frames: Vec<_> = reader.map(Result::unwrap).collect();
+    assert_eq!(frames.len(), 3);
+    for (frame_out, i) in frames.iter().zip([1, 2, 10]) {
+        assert_eq!(frame_out.duration_ms, 50);
+        assert!(frame_out.pixels == frame(i).into_raw(), "frame {i}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn test_qoiseq_bad_input() {
+    let dir = temp_path("mismatched");
+    let out = temp_path("mismatched.qoi");
+    fs::create_dir_all(&dir).unwrap();
+    frame(0).save(dir.join("0.png")).unwrap();
+    RgbImage::new(4, 4).save(dir.join("1.png")).unwrap();
+    let output = qoiseq(&[dir.to_str().unwrap(), out.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("the first frame is 8x4"));
+
+    let empty = temp_path("empty");
+    fs::create_dir_all(&empty).unwrap();
+    let output = qoiseq(&[empty.to_str().unwrap(), out.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no numbered PNG frames"));
+
+    let output = qoiseq(&["--duration", "soon", "in", "out.qoi"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(empty).unwrap();
+    let _ = fs::remove_file(out);
+}
@@ -0,0 +1,110 @@
+use image::io::Reader as ImageReader;
+use rustqoi::{decode, encode, ColorSpace, QoiError, QoiHeader, QoiReader};
+use std::{fs::File, io::Read};
+
+#[test]
+fn test_reader_rows_go() {
+    let mut encoded = Vec::new();
+    File::open("../go.qoi")
+        .unwrap()
+        .read_to_end(&mut encoded)
+        .unwrap();
+    let (header, expected) = decode(&encoded).unwrap();
+
+    let mut reader = QoiReader::new(File::open("../go.qoi").unwrap()).unwrap();
+    assert_eq!(reader.header(), &header);
+    let mut decoded = Vec::new();
+    while let Some(row) = reader.read_row().unwrap() {
+        assert_eq!(row.len(), header.width as usize * header.channels as usize);
+        decoded.extend_from_slice(row);
+    }
+    assert_eq!(reader.pixels_left(), 0);
+    assert!(decoded == expected, "data not the same");
+}
+
+#[test]
+fn test_reader_small_buffers() {
+    let img = ImageReader::open("../go.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_rgb8();
+    let data = img.to_vec();
+    let encoded = encode(
+        &data,
+        QoiHeader::new(img.width(), img.height(), 3, ColorSpace::Srgb),
+    )
+    .unwrap();
+
+    let mut reader = QoiReader::new(encoded.as_slice()).unwrap();
+    let mut decoded = Vec::new();
+    // 7 bytes hold 2 pixels, which splits runs across calls.
+    let mut buf = [0; 7];
+    loop {
+        let n = reader.read_pixels(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        decoded.extend_from_slice(&buf[..n * 3]);
+    }
+    assert!(decoded == data, "data not the same");
+}
+
+#[test]
+fn test_reader_empty() {
+    let data = vec![
+        113, 111, 105, 102, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+    ];
+    let mut reader = QoiReader::new(data.as_slice()).unwrap();
+    assert_eq!(reader.read_row(), Ok(None));
+}
+
+#[test]
+fn test_reader_back_to_back() {
+    let first_header = QoiHeader::new(3, 2, 4, ColorSpace::Srgb);
+    let first: Vec<u8> = (0..24).collect();
+    let second_header = QoiHeader::new(2, 2, 3, ColorSpace::Linear);
+    let second: Vec<u8> = (100..112).collect();
+    let stream = [
+        encode(&first, first_header).unwrap(),
+        encode(&second, second_header).unwrap(),
+    ]
+    .concat();
+
+    let mut reader = QoiReader::new(stream.as_slice()).unwrap();
+    let mut decoded = vec![0; first.len()];
+    assert_eq!(reader.read_pixels(&mut decoded), Ok(6));
+    assert_eq!(decoded, first);
+
+    let mut reader = QoiReader::new(reader.into_inner()).unwrap();
+    assert_eq!(reader.header(), &second_header);
+    let mut decoded = vec![0; second.len()];
+    assert_eq!(reader.read_pixels(&mut decoded), Ok(4));
+    assert_eq!(decoded, second);
+    assert_eq!(reader.into_inner().bytes().count(), 0);
+}
+
+#[test]
+fn test_reader_errors() {
+    let header = [113, 111, 105, 102, 0, 0, 0, 2, 0, 0, 0, 1, 4, 1];
+    assert_eq!(
+        QoiReader::new(&header[..10]).err(),
+        Some(QoiError::TooShort)
+    );
+
+    let truncated = [&header[..], &[255, 1, 2]].concat();
+    let mut reader = QoiReader::new(truncated.as_slice()).unwrap();
+    assert_eq!(reader.read_row(), Err(QoiError::TruncatedOp));
+
+    let short = [&header[..], &[0b11000000]].concat();
+    let mut reader = QoiReader::new(short.as_slice()).unwrap();
+    assert_eq!(reader.read_row(), Err(QoiError::TooFewPixels));
+
+    let long = [&header[..], &[0b11000010]].concat();
+    let mut reader = QoiReader::new(long.as_slice()).unwrap();
+    assert_eq!(reader.read_row(), Err(QoiError::TooManyPixels));
+
+    let bad_footer = [&header[..], &[0b11000001, 0, 0, 0, 0, 0, 0, 0, 0]].concat();
+    let mut reader = QoiReader::new(bad_footer.as_slice()).unwrap();
+    assert_eq!(reader.read_row(), Err(QoiError::BadFooter));
+}
@@ -0,0 +1,229 @@
+use rustqoi::{
+    decode, encode, is_sequence, ColorSpace, DecodeOptions, Decoder, Frame, PixelFormat, QoiError,
+    QoiHeader, SequenceReader, SequenceWriter,
+};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// Frames of a small square moving across a gray background.
+fn frames(header: &QoiHeader, count: u32) -> Vec<Vec<u8>> {
+    let channels = header.channels as usize;
+    (0..count)
+        .map(|i| {
+            let mut frame = [128, 128, 128, 255][..channels].repeat(header.n_pixels().unwrap());
+            for y in 2..6 {
+                for x in i..i + 4 {
+                    let at = (y * header.width + x) as usize * channels;
+                    frame[at..at + 3].copy_from_slice(&[255, i as u8 * 20, 0]);
+                }
+            }
+            frame
+        })
+        .collect()
+}
+
+fn write_sequence(header: QoiHeader, frames: &[Vec<u8>], loops: u32) -> Vec<u8> {
+    let mut writer = SequenceWriter::new(Cursor::new(Vec::new()), header, loops).unwrap();
+    for (i, frame) in frames.iter().enumerate() {
+        writer.write_frame(frame, 40 + i as u32).unwrap();
+    }
+    assert_eq!(writer.frames(), frames.len() as u32);
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_sequence_round_trip() {
+    for channels in [3, 4] {
+        let header = QoiHeader::new(16, 8, channels, ColorSpace::Srgb);
+        let frames = frames(&header, 10);
+        let encoded = write_sequence(header, &frames, 3);
+        assert!(is_sequence(&encoded));
+
+        let mut reader = SequenceReader::new(encoded.as_slice()).unwrap();
+        assert_eq!(*reader.header(), header);
+        assert_eq!((reader.frames(), reader.loops()), (10, 3));
+        for (i, expected) in frames.iter().enumerate() {
+            assert_eq!(reader.frames_left(), 10 - i as u32);
+            let frame = reader.next().unwrap().unwrap();
+            assert_eq!(frame.duration_ms, 40 + i as u32);
+            assert!(frame.pixels == *expected, "frame {i}");
+        }
+        assert_eq!(reader.next(), None);
+    }
+}
+
+#[test]
+fn test_sequence_frames_are_qoi_files() {
+    let header = QoiHeader::new(16, 8, 4, ColorSpace::Linear);
+    let frames = frames(&header, 2);
+    let encoded = write_sequence(header, &frames, 0);
+
+    let first = &encoded[26 + 12..];
+    let len = u64::from_be_bytes(encoded[30..38].try_into().unwrap()) as usize;
+    assert!(first[..len] == encode(&frames[0], header).unwrap());
+    assert_eq!(decode(&first[..len]).unwrap(), (header, frames[0].clone()));
+}
+
+#[test]
+fn test_sequence_reads_lazily() {
+    /// Counts the bytes handed out, to see how far the reader got.
+    struct Counting<'a>(&'a [u8], usize);
+
+    impl Read for Counting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.read(buf)?;
+            self.1 += n;
+            Ok(n)
+        }
+    }
+
+    // Noise does not compress, so every frame is far larger than the read buffer.
+    let header = QoiHeader::new(64, 64, 4, ColorSpace::Srgb);
+    let mut noise = 1u32;
+    let frames: Vec<Vec<u8>> = (0..5)
+        .map(|_| {
+            (0..header.decoded_size().unwrap())
+                .map(|_| {
+                    noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (noise >> 16) as u8
+                })
+                .collect()
+        })
+        .collect();
+    let encoded = write_sequence(header, &frames, 0);
+
+    let mut counting = Counting(&encoded, 0);
+    let mut reader = SequenceReader::new(&mut counting).unwrap();
+    assert!(reader.next().unwrap().unwrap().pixels == frames[0]);
+    drop(reader);
+    assert!(counting.1 < encoded.len() / 2, "read {} bytes", counting.1);
+}
+
+#[test]
+fn test_sequence_delta_frames() {
+    for interval in [0, 1, 4] {
+        let header = QoiHeader::new(64, 32, 4, ColorSpace::Srgb);
+        let frames = frames(&header, 10);
+        let mut writer = SequenceWriter::new(Cursor::new(Vec::new()), header, 0)
+            .unwrap()
+            .with_keyframe_interval(interval);
+        for frame in &frames {
+            writer.write_frame(frame, 40).unwrap();
+        }
+        let encoded = writer.finish().unwrap().into_inner();
+        let plain = write_sequence(header, &frames, 0);
+        assert_eq!(encoded.len() < plain.len(), interval != 1);
+
+        let options = DecodeOptions {
+            format: Some(PixelFormat::Bgra),
+            premultiplied: true,
+            ..DecodeOptions::default()
+        };
+        let reader = SequenceReader::with_options(encoded.as_slice(), options).unwrap();
+        for (frame, expected) in reader.zip(&frames) {
+            let expected = Decoder::with_options(&encode(expected, header).unwrap(), options)
+                .unwrap()
+                .decode()
+                .unwrap();
+            assert!(frame.unwrap().pixels == expected, "interval {interval}");
+        }
+    }
+}
+
+#[test]
+fn test_sequence_with_options() {
+    let header = QoiHeader::new(16, 8, 4, ColorSpace::Srgb);
+    let frames = frames(&header, 2);
+    let encoded = write_sequence(header, &frames, 0);
+    let options = DecodeOptions {
+        format: Some(PixelFormat::Bgra),
+        ..DecodeOptions::default()
+    };
+    let reader = SequenceReader::with_options(encoded.as_slice(), options).unwrap();
+    let decoded: Vec<Frame> = reader.collect::<Result<_, _>>().unwrap();
+    for (frame, expected) in decoded.iter().zip(&frames) {
+        for (bgra, rgba) in frame.pixels.chunks_exact(4).zip(expected.chunks_exact(4)) {
+            assert_eq!(bgra, [rgba[2], rgba[1], rgba[0], rgba[3]]);
+        }
+    }
+
+    let limits = DecodeOptions {
+        max_width: 8,
+        ..DecodeOptions::default()
+    };
+    assert_eq!(
+        SequenceReader::with_options(encoded.as_slice(), limits).err(),
+        Some(QoiError::LimitExceeded)
+    );
+}
+
+#[test]
+fn test_sequence_appends_after_existing_data() {
+    let header = QoiHeader::new(16, 8, 3, ColorSpace::Srgb);
+    let frames = frames(&header, 3);
+    let mut cursor = Cursor::new(b"prefix".to_vec());
+    cursor.seek(SeekFrom::End(0)).unwrap();
+    let mut writer = SequenceWriter::new(cursor, header, 1).unwrap();
+    for frame in &frames {
+        writer.write_frame(frame, 100).unwrap();
+    }
+    let data = writer.finish().unwrap().into_inner();
+    assert!(data.starts_with(b"prefix"));
+
+    let reader = SequenceReader::new(&data[6..]).unwrap();
+    assert_eq!(reader.frames(), 3);
+    assert_eq!(reader.count(), 3);
+}
+
+#[test]
+fn test_sequence_errors() {
+    let header = QoiHeader::new(16, 8, 4, ColorSpace::Srgb);
+    let frames = frames(&header, 2);
+
+    let mut writer = SequenceWriter::new(Cursor::new(Vec::new()), header, 0).unwrap();
+    assert_eq!(
+        writer.write_frame(&frames[0][4..], 100),
+        Err(QoiError::DataSizeMismatch {
+            expected: frames[0].len(),
+            actual: frames[0].len() - 4
+        })
+    );
+    assert_eq!(writer.frames(), 0);
+    assert_eq!(
+        SequenceWriter::new(
+            Cursor::new(Vec::new()),
+            QoiHeader {
+                channels: 2,
+                ..header
+            },
+            0
+        )
+        .err(),
+        Some(QoiError::InvalidChannels(2))
+    );
+
+    let encoded = write_sequence(header, &frames, 0);
+    assert_eq!(
+        SequenceReader::new(&encoded[..20]).err(),
+        Some(QoiError::TooShort)
+    );
+    assert_eq!(
+        SequenceReader::new(&encode(&frames[0], header).unwrap()[..]).err(),
+        Some(QoiError::BadMagic)
+    );
+
+    // The second frame is cut short.
+    let mut reader = SequenceReader::new(&encoded[..encoded.len() - 10]).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(reader.next(), Some(Err(QoiError::TooShort)));
+    assert_eq!(reader.next(), None);
+
+    // A frame of another size.
+    let mut mismatched = encoded[..26].to_vec();
+    let small = QoiHeader::new(8, 8, 4, ColorSpace::Srgb);
+    let frame = encode(&frames[0][..small.decoded_size().unwrap()], small).unwrap();
+    mismatched.extend_from_slice(&100u32.to_be_bytes());
+    mismatched.extend_from_slice(&(frame.len() as u64).to_be_bytes());
+    mismatched.extend_from_slice(&frame);
+    let mut reader = SequenceReader::new(mismatched.as_slice()).unwrap();
+    assert_eq!(reader.next(), Some(Err(QoiError::BadSequence)));
+}
@@ -0,0 +1,128 @@
+use image::io::Reader as ImageReader;
+use rustqoi::{
+    decode, decode_strips, encode, encode_strips, is_strips, ColorSpace, DecodeOptions,
+    PixelFormat, QoiError, QoiHeader, StripDecoder, StripEncoder,
+};
+
+fn go_rgba() -> (QoiHeader, Vec<u8>) {
+    let img = ImageReader::open("../go.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_rgba8();
+    let header = QoiHeader::new(img.width(), img.height(), 4, ColorSpace::Srgb);
+    (header, img.to_vec())
+}
+
+#[test]
+fn test_strips_round_trip_go() {
+    let (header, data) = go_rgba();
+    for rows in [1, 7, 64, header.height, header.height + 1] {
+        let encoded = StripEncoder::new(header)
+            .with_rows_per_strip(rows)
+            .encode(&data)
+            .unwrap();
+        assert!(is_strips(&encoded));
+        let decoder = StripDecoder::new(&encoded).unwrap();
+        assert_eq!(*decoder.header(), header);
+        assert_eq!(decoder.strips().len() as u32, header.height.div_ceil(rows));
+        assert!(decoder.decode().unwrap() == data, "{rows} rows per strip");
+
+        // Every strip is a plain QOI file of its own.
+        for (i, strip) in decoder.strips().iter().enumerate() {
+            let (strip_header, pixels) = decode(strip).unwrap();
+            let first_row = i * rows as usize;
+            let row_bytes = header.width as usize * 4;
+            let start = first_row * row_bytes;
+            assert!(pixels == data[start..start + strip_header.height as usize * row_bytes]);
+        }
+    }
+}
+
+#[test]
+fn test_strips_to_qoi() {
+    let (header, data) = go_rgba();
+    let encoded = encode_strips(&data, header).unwrap();
+    let options = DecodeOptions {
+        format: Some(PixelFormat::Bgra),
+        ..DecodeOptions::default()
+    };
+    let decoder = StripDecoder::with_options(&encoded, options).unwrap();
+    assert!(decoder.to_qoi().unwrap() == encode(&data, header).unwrap());
+
+    let bgra = decoder.decode().unwrap();
+    for (rgba, bgra) in data.chunks_exact(4).zip(bgra.chunks_exact(4)) {
+        assert_eq!([rgba[2], rgba[1], rgba[0], rgba[3]], bgra);
+    }
+}
+
+#[test]
+fn test_strips_empty() {
+    let header = QoiHeader::new(0, 0, 3, ColorSpace::Linear);
+    let encoded = encode_strips(&[], header).unwrap();
+    assert_eq!(encoded.len(), 26);
+    assert_eq!(decode_strips(&encoded), Ok((header, vec![])));
+
+    // Without pixels every strip is still a QOI file of the right height.
+    let header = QoiHeader::new(0, 5, 4, ColorSpace::Srgb);
+    let encoded = StripEncoder::new(header)
+        .with_rows_per_strip(2)
+        .encode(&[])
+        .unwrap();
+    assert_eq!(decode_strips(&encoded), Ok((header, vec![])));
+    // Three strips of a header and an end marker each, after the 50 bytes of the table.
+    assert_eq!(encoded.len(), 50 + 3 * 22);
+    let mut bad_magic = encoded.clone();
+    bad_magic[72] = b'x';
+    assert_eq!(decode_strips(&bad_magic), Err(QoiError::BadMagic));
+    let mut bad_footer = encoded.clone();
+    *bad_footer.last_mut().unwrap() = 2;
+    assert_eq!(decode_strips(&bad_footer), Err(QoiError::BadFooter));
+    let mut wrong_height = encoded.clone();
+    wrong_height[50 + 11] = 1;
+    assert_eq!(decode_strips(&wrong_height), Err(QoiError::BadStripTable));
+}
+
+#[test]
+fn test_strips_errors() {
+    let header = QoiHeader::new(2, 3, 4, ColorSpace::Srgb);
+    let data = [[1, 2, 3, 255], [4, 5, 6, 255]].concat().repeat(3);
+    let encoded = StripEncoder::new(header)
+        .with_rows_per_strip(2)
+        .encode(&data)
+        .unwrap();
+    assert_eq!(decode_strips(&encoded).unwrap().1, data);
+
+    let plain = encode(&data, header).unwrap();
+    assert_eq!(decode_strips(&plain), Err(QoiError::BadMagic));
+    assert_eq!(decode_strips(&encoded[..30]), Err(QoiError::TooShort));
+
+    let mut wrong_count = encoded.clone();
+    wrong_count[25] = 3;
+    assert_eq!(decode_strips(&wrong_count), Err(QoiError::BadStripTable));
+
+    // The second strip starting before the first.
+    let mut wrong_offset = encoded.clone();
+    wrong_offset[41] = 0;
+    assert_eq!(decode_strips(&wrong_offset), Err(QoiError::BadStripTable));
+
+    // A strip whose header disagrees with the container.
+    let mut wrong_strip = encoded.clone();
+    wrong_strip[42 + 12] = 3;
+    assert_eq!(decode_strips(&wrong_strip), Err(QoiError::BadStripTable));
+
+    // One strip may hold more rows than the image has.
+    let one_strip = StripEncoder::new(header)
+        .with_rows_per_strip(u32::MAX)
+        .encode(&data)
+        .unwrap();
+    assert_eq!(decode_strips(&one_strip).unwrap().1, data);
+
+    let huge = QoiHeader::new(u32::MAX, u32::MAX, 4, ColorSpace::Srgb);
+    assert_eq!(
+        StripEncoder::new(huge).encode(&data),
+        Err(QoiError::LimitExceeded)
+    );
+    let no_rows = QoiHeader::new(u32::MAX, 0, 4, ColorSpace::Srgb);
+    assert!(is_strips(&StripEncoder::new(no_rows).encode(&[]).unwrap()));
+}
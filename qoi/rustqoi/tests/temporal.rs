@@ -0,0 +1,213 @@
+mod common;
+
+use common::Rng;
+use rustqoi::{
+    decode, encode, is_delta, ColorSpace, QoiError, QoiHeader, TemporalDecoder, TemporalEncoder,
+};
+
+/// Encodes `frames` as one stream, checks that they decode back and returns their sizes.
+fn round_trip(header: QoiHeader, frames: &[Vec<u8>]) -> Vec<usize> {
+    let mut encoder = TemporalEncoder::new(header);
+    let mut decoder = TemporalDecoder::new();
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let encoded = encoder.encode_frame(frame).unwrap();
+            assert_eq!(is_delta(&encoded), i > 0);
+            let (decoded_header, pixels) = decoder.decode_frame(&encoded).unwrap();
+            assert_eq!(decoded_header, header);
+            assert!(pixels == frame.as_slice(), "frame {i}");
+            encoded.len()
+        })
+        .collect()
+}
+
+#[test]
+fn test_temporal_screen_capture() {
+    // A desktop where only a cursor moves and a clock ticks.
+    let header = QoiHeader::new(320, 200, 4, ColorSpace::Srgb);
+    let mut rng = Rng(7);
+    let desktop: Vec<u8> = (0..header.n_pixels().unwrap())
+        .flat_map(|i| [(i % 320) as u8, (i / 320) as u8, rng.below(4) as u8, 255])
+        .collect();
+    let frames: Vec<Vec<u8>> = (0..8)
+        .map(|t| {
+            let mut frame = desktop.clone();
+            for y in 0..8 {
+                let at = ((50 + y + t * 3) * 320 + 40 + t * 5) * 4;
+                frame[at..at + 16].copy_from_slice(&[255; 16]);
+            }
+            let clock = (190 * 320 + 300) * 4;
+            frame[clock..clock + 4].copy_from_slice(&[t as u8 * 30, 0, 0, 255]);
+            frame
+        })
+        .collect();
+
+    let sizes = round_trip(header, &frames);
+    let plain = encode(&frames[1], header).unwrap().len();
+    for size in &sizes[1..] {
+        assert!(size * 50 < plain, "delta {size} bytes, plain {plain}");
+    }
+}
+
+#[test]
+fn test_temporal_unchanged_frame() {
+    let header = QoiHeader::new(1920, 1080, 3, ColorSpace::Srgb);
+    let frame: Vec<u8> = (0..header.decoded_size().unwrap())
+        .map(|i| (i % 251) as u8)
+        .collect();
+    let sizes = round_trip(header, &[frame.clone(), frame]);
+    // The header, a single long skip and the end marker.
+    assert!(sizes[1] <= 14 + 4 + 8, "{} bytes", sizes[1]);
+}
+
+#[test]
+fn test_temporal_every_op() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for channels in [3, 4] {
+        let header = QoiHeader::new(64, 48, channels, ColorSpace::Linear);
+        let mut frame: Vec<u8> = (0..header.decoded_size().unwrap())
+            .map(|_| rng.below(256) as u8)
+            .collect();
+        let mut frames = vec![frame.clone()];
+        for _ in 0..6 {
+            for pixel in frame.chunks_exact_mut(channels as usize) {
+                match rng.below(8) {
+                    // Small changes for diff, larger ones for luma, any for rgb and rgba.
+                    0 => pixel[0] = pixel[0].wrapping_add(1),
+                    1 => {
+                        let dg = rng.below(40) as u8;
+                        pixel[0] = pixel[0].wrapping_add(dg).wrapping_add(3);
+                        pixel[1] = pixel[1].wrapping_add(dg);
+                        pixel[2] = pixel[2].wrapping_add(dg).wrapping_sub(5);
+                    }
+                    2 => pixel[..3].copy_from_slice(&rng.next().to_le_bytes()[..3]),
+                    3 => pixel.copy_from_slice(&rng.next().to_le_bytes()[..pixel.len()]),
+                    // A few colors that keep coming back, for the index.
+                    4 => pixel.copy_from_slice(
+                        &[[9, 8, 7, 6], [1, 2, 3, 4]][rng.below(2) as usize][..pixel.len()],
+                    ),
+                    _ => {}
+                }
+            }
+            frames.push(frame.clone());
+        }
+        round_trip(header, &frames);
+    }
+}
+
+#[test]
+fn test_temporal_skip_lengths() {
+    let header = QoiHeader::new(4096, 1, 4, ColorSpace::Srgb);
+    let first = [10, 20, 30, 255].repeat(header.n_pixels().unwrap());
+    for gap in [
+        1,
+        60,
+        61,
+        62,
+        63,
+        62 + 127,
+        62 + 128,
+        62 + 16383,
+        62 + 16384,
+    ] {
+        let mut second = first.clone();
+        let mut at = 0;
+        while at < header.n_pixels().unwrap() {
+            second[at * 4] = 99;
+            at += gap + 1;
+        }
+        round_trip(header, &[first.clone(), second]);
+    }
+}
+
+#[test]
+fn test_temporal_reset() {
+    let header = QoiHeader::new(8, 8, 4, ColorSpace::Srgb);
+    let frame = [1, 2, 3, 4].repeat(64);
+    let mut encoder = TemporalEncoder::new(header);
+    encoder.encode_frame(&frame).unwrap();
+    assert!(is_delta(&encoder.encode_frame(&frame).unwrap()));
+    encoder.reset();
+    let key = encoder.encode_frame(&frame).unwrap();
+    assert_eq!(decode(&key).unwrap(), (header, frame));
+}
+
+#[test]
+fn test_temporal_errors() {
+    let header = QoiHeader::new(8, 8, 4, ColorSpace::Srgb);
+    let first = [1, 2, 3, 4].repeat(64);
+    let mut second = first.clone();
+    second[100] = 200;
+    let mut encoder = TemporalEncoder::new(header);
+    let key = encoder.encode_frame(&first).unwrap();
+    let delta = encoder.encode_frame(&second).unwrap();
+    assert_eq!(
+        encoder.encode_frame(&first[4..]),
+        Err(QoiError::DataSizeMismatch {
+            expected: 256,
+            actual: 252
+        })
+    );
+    let huge = QoiHeader::new(u32::MAX, u32::MAX, 4, ColorSpace::Srgb);
+    assert_eq!(
+        TemporalEncoder::new(huge).encode_frame(&first),
+        Err(QoiError::LimitExceeded)
+    );
+
+    // A delta frame needs the frame before it.
+    let mut decoder = TemporalDecoder::new();
+    assert_eq!(
+        decoder.decode_frame(&delta).err(),
+        Some(QoiError::MissingReference)
+    );
+    let other = QoiHeader::new(4, 16, 4, ColorSpace::Srgb);
+    decoder
+        .decode_frame(&encode(&first, other).unwrap())
+        .unwrap();
+    assert_eq!(
+        decoder.decode_frame(&delta).err(),
+        Some(QoiError::MissingReference)
+    );
+
+    decoder.decode_frame(&key).unwrap();
+    let cut = [&delta[..delta.len() - 10], &delta[delta.len() - 8..]].concat();
+    assert!(decoder.decode_frame(&cut).is_err());
+    // A failed delta frame may have left the reference half updated, so it is dropped.
+    assert_eq!(
+        decoder.decode_frame(&delta).err(),
+        Some(QoiError::MissingReference)
+    );
+
+    decoder.decode_frame(&key).unwrap();
+    assert_eq!(
+        decoder.decode_frame(&delta[..delta.len() - 1]).err(),
+        Some(QoiError::BadFooter)
+    );
+    decoder.decode_frame(&key).unwrap();
+    let mut too_many = delta[..delta.len() - 8].to_vec();
+    too_many.extend_from_slice(&[0xc0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(
+        decoder.decode_frame(&too_many).err(),
+        Some(QoiError::TooManyPixels)
+    );
+    decoder.decode_frame(&key).unwrap();
+    let mut too_few = delta[..14].to_vec();
+    too_few.extend_from_slice(&[0xc0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(
+        decoder.decode_frame(&too_few).err(),
+        Some(QoiError::TooFewPixels)
+    );
+    decoder.decode_frame(&key).unwrap();
+    let mut huge_skip = delta[..14].to_vec();
+    huge_skip.extend_from_slice(&[0xfd, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    huge_skip.extend_from_slice(&[0x7f, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(
+        decoder.decode_frame(&huge_skip).err(),
+        Some(QoiError::TooManyPixels)
+    );
+
+    // Plain decoders do not mistake a delta frame for a QOI file.
+    assert_eq!(decode(&delta), Err(QoiError::BadMagic));
+}
@@ -0,0 +1,71 @@
+use image::io::Reader as ImageReader;
+use rustqoi::{encode, ColorSpace, QoiError, QoiHeader, QoiWriter};
+
+#[test]
+fn test_writer_rows_go() {
+    let img = ImageReader::open("../go.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_rgba8();
+    let data = img.to_vec();
+    let header = QoiHeader::new(img.width(), img.height(), 4, ColorSpace::Linear);
+    let expected = encode(&data, header).unwrap();
+
+    let mut writer = QoiWriter::new(Vec::new(), header).unwrap();
+    for row in data.chunks_exact(img.width() as usize * 4) {
+        writer.write_row(row).unwrap();
+    }
+    let encoded = writer.finish().unwrap();
+    assert!(encoded == expected, "streamed output differs from encode");
+}
+
+#[test]
+fn test_writer_arbitrary_chunks() {
+    let black: [u8; 3] = [0, 0, 0];
+    let white: [u8; 3] = [255, 255, 255];
+    let data = [vec![black; 100], vec![white; 100], vec![black; 30]]
+        .concat()
+        .concat();
+    let header = QoiHeader::new(23, 10, 3, ColorSpace::Srgb);
+    let expected = encode(&data, header).unwrap();
+
+    let mut writer = QoiWriter::new(Vec::new(), header).unwrap();
+    // Chunks of 7 pixels split the runs across calls.
+    for chunk in data.chunks(7 * 3) {
+        writer.write_row(chunk).unwrap();
+    }
+    assert_eq!(writer.finish(), Ok(expected));
+}
+
+#[test]
+fn test_writer_pixel_count_mismatch() {
+    let header = QoiHeader::new(2, 1, 4, ColorSpace::Srgb);
+
+    let mut writer = QoiWriter::new(Vec::new(), header).unwrap();
+    assert_eq!(writer.write_row(&[0; 12]), Err(QoiError::TooManyPixels));
+    writer.write_row(&[0; 4]).unwrap();
+    assert_eq!(writer.pixels_left(), 1);
+    assert_eq!(writer.finish(), Err(QoiError::TooFewPixels));
+
+    let mut writer = QoiWriter::new(Vec::new(), header).unwrap();
+    assert_eq!(
+        writer.write_row(&[0; 5]),
+        Err(QoiError::DataSizeMismatch {
+            expected: 8,
+            actual: 5
+        })
+    );
+
+    // The writer never holds the whole image, so any header that counts its pixels will do.
+    let huge = QoiHeader::new(u32::MAX, u32::MAX, 4, ColorSpace::Srgb);
+    let writer = QoiWriter::new(Vec::new(), huge).unwrap();
+    assert_eq!(writer.pixels_left() as u64, u64::from(u32::MAX).pow(2));
+    let mut out = Vec::new();
+    let invalid = QoiHeader::new(u32::MAX, u32::MAX, 2, ColorSpace::Srgb);
+    assert_eq!(
+        QoiWriter::new(&mut out, invalid).err(),
+        Some(QoiError::InvalidChannels(2))
+    );
+    assert!(out.is_empty());
+}